@@ -11,12 +11,13 @@ describe('Merkle tree program', () => {
   const port = process.env['RPC_PORT'];
   const connection = new Connection(`http://127.0.0.1:${port}`, 'confirmed');
   const SEED = 'tree';
+  const TREE_ID = Buffer.alloc(8); // u64 tree id 0, little-endian
 
   test("Insert leaf", async () => {
     console.log("Program ID:", programId.toBase58());
 
     const [pda, bump] = await PublicKey.findProgramAddressSync(
-      [Buffer.from(SEED), wallet.publicKey.toBuffer()], // seeds
+      [Buffer.from(SEED), wallet.publicKey.toBuffer(), TREE_ID], // seeds
       programId
     );
 
@@ -32,7 +33,7 @@ describe('Merkle tree program', () => {
         { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false }
       ],
       programId: new PublicKey(programId),
-      data: Buffer.from([0, 3])
+      data: Buffer.concat([Buffer.from([0, 3]), TREE_ID, Buffer.from([0, 0, 0, 0, 0])]) // no leaf reuse, payer is the authority, SHA-256, no domain separation, no root history
     });
 
     console.log("Tree account sent to instruction ", pda.toBase58());
@@ -40,7 +41,7 @@ describe('Merkle tree program', () => {
     const leaf = sha256(Buffer.from('LeafA'));
     const addLeafInstruction = new TransactionInstruction({
       keys: [
-        { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+        { pubkey: wallet.publicKey, isSigner: true, isWritable: false },
         { pubkey: pda, isSigner: false, isWritable: true },
       ],
       programId: new PublicKey(programId),
@@ -55,7 +56,8 @@ describe('Merkle tree program', () => {
 
     const tx = await connection.getParsedTransaction(sig);
     const logs = tx?.meta?.logMessages || [];
-    const selectedEvents = logs.filter(line => ['event:CreateTree', 'event:LeafInserted'].some(event => line.includes(event)));
+    // TreeCreated and LeafInserted, Borsh-encoded as MerkleTreeEvent
+    const selectedEvents = logs.filter(line => line.startsWith('Program data: '));
     expect(selectedEvents).toMatchSnapshot();
   });
 });
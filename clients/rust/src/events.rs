@@ -0,0 +1,149 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+#[cfg(feature = "transaction-status")]
+use solana_transaction_status::option_serializer::OptionSerializer;
+#[cfg(feature = "transaction-status")]
+use solana_transaction_status::{
+    EncodedTransaction, EncodedTransactionWithStatusMeta, UiInstruction, UiMessage,
+    UiParsedInstruction,
+};
+
+use crate::instructions::EmitEventInstructionData;
+use crate::types::MerkleTreeEvent;
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+/// Seed of the PDA the program signs EmitEvent self-CPIs with.
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"event_authority";
+
+pub fn find_event_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &crate::ID)
+}
+
+/// Remaining accounts that make an instruction emit its event as an EmitEvent
+/// inner instruction instead of a log line, e.g.
+/// `InsertLeafBuilder::new()...add_remaining_accounts(&event_cpi_accounts())`.
+pub fn event_cpi_accounts() -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(find_event_authority().0, false),
+        AccountMeta::new_readonly(crate::ID, false),
+    ]
+}
+
+/// Decodes the Borsh payload of a single `sol_log_data` entry.
+pub fn decode_event(data: &[u8]) -> std::io::Result<MerkleTreeEvent> {
+    MerkleTreeEvent::try_from_slice(data)
+}
+
+/// Events logged by this program in a transaction's log messages, in order.
+/// `Program data:` lines logged by other programs, including ones invoked
+/// through CPI, are skipped.
+pub fn events_from_logs(logs: &[String]) -> Vec<MerkleTreeEvent> {
+    let program_id = crate::ID.to_string();
+    let mut invoked = Vec::new();
+    let mut events = Vec::new();
+    for log in logs {
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+            if invoked.last() != Some(&program_id.as_str()) {
+                continue;
+            }
+            let event = data
+                .split(' ')
+                .map(|entry| STANDARD.decode(entry).ok())
+                .collect::<Option<Vec<_>>>()
+                .and_then(|entries| decode_event(&entries.concat()).ok());
+            if let Some(event) = event {
+                events.push(event);
+            }
+        } else if let Some(rest) = log.strip_prefix("Program ") {
+            let mut words = rest.split(' ');
+            match (words.next(), words.next()) {
+                (Some(id), Some("invoke")) => invoked.push(id),
+                (Some(_), Some("success" | "failed:")) => {
+                    invoked.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    events
+}
+
+/// Decodes the data of an EmitEvent instruction, or returns `None` for any other
+/// instruction of the program.
+pub fn decode_event_instruction(data: &[u8]) -> Option<MerkleTreeEvent> {
+    let discriminator = EmitEventInstructionData::new().try_to_vec().ok()?;
+    let event = data.strip_prefix(discriminator.as_slice())?;
+    decode_event(event).ok()
+}
+
+/// Events emitted as EmitEvent inner instructions of a transaction fetched from
+/// RPC (`getTransaction` or `getBlock`), in order. Unlike log lines, these are
+/// kept when the logs of the transaction are truncated. Needs the
+/// `transaction-status` feature.
+#[cfg(feature = "transaction-status")]
+pub fn events_from_transaction(
+    transaction: &EncodedTransactionWithStatusMeta,
+) -> Vec<MerkleTreeEvent> {
+    let Some(meta) = &transaction.meta else {
+        return Vec::new();
+    };
+    let OptionSerializer::Some(inner_instructions) = &meta.inner_instructions else {
+        return Vec::new();
+    };
+    let mut account_keys = match &transaction.transaction {
+        EncodedTransaction::Json(ui_transaction) => match &ui_transaction.message {
+            UiMessage::Raw(message) => message.account_keys.clone(),
+            UiMessage::Parsed(message) => message
+                .account_keys
+                .iter()
+                .map(|key| key.pubkey.clone())
+                .collect(),
+        },
+        EncodedTransaction::Accounts(list) => list
+            .account_keys
+            .iter()
+            .map(|key| key.pubkey.clone())
+            .collect(),
+        encoded => encoded
+            .decode()
+            .map(|decoded| {
+                decoded
+                    .message
+                    .static_account_keys()
+                    .iter()
+                    .map(Pubkey::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    if let OptionSerializer::Some(loaded_addresses) = &meta.loaded_addresses {
+        account_keys.extend(loaded_addresses.writable.iter().cloned());
+        account_keys.extend(loaded_addresses.readonly.iter().cloned());
+    }
+
+    let program_id = crate::ID.to_string();
+    inner_instructions
+        .iter()
+        .flat_map(|inner| &inner.instructions)
+        .filter_map(|instruction| {
+            let (program, data) = match instruction {
+                UiInstruction::Compiled(compiled) => (
+                    account_keys.get(compiled.program_id_index as usize)?,
+                    &compiled.data,
+                ),
+                UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(decoded)) => {
+                    (&decoded.program_id, &decoded.data)
+                }
+                UiInstruction::Parsed(UiParsedInstruction::Parsed(_)) => return None,
+            };
+            if *program != program_id {
+                return None;
+            }
+            decode_event_instruction(&bs58::decode(data).into_vec().ok()?)
+        })
+        .collect()
+}
@@ -0,0 +1,92 @@
+//! This code was AUTOGENERATED using the kinobi library.
+//! Please DO NOT EDIT THIS FILE, instead use visitors
+//! to add features, then rerun kinobi to update it.
+//!
+//! [https://github.com/metaplex-foundation/kinobi]
+//!
+
+use crate::generated::types::ChangeLog;
+use crate::generated::types::HashKind;
+#[cfg(feature = "anchor")]
+use anchor_lang::prelude::{AnchorDeserialize, AnchorSerialize};
+#[cfg(not(feature = "anchor"))]
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(not(feature = "anchor"), derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "anchor", derive(AnchorSerialize, AnchorDeserialize))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConcurrentMerkleTree {
+    pub max_depth: u8,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "serde_with::As::<serde_with::DisplayFromStr>")
+    )]
+    pub authority: Pubkey,
+    pub hash_kind: HashKind,
+    pub domain_separated: bool,
+    pub sequence_number: u64,
+    pub next_leaf_index: u32,
+    pub active_index: u32,
+    pub buffer_size: u32,
+    pub rightmost_proof: Vec<[u8; 32]>,
+    pub change_logs: Vec<ChangeLog>,
+    pub canopy_depth: u8,
+    pub canopy: Vec<[u8; 32]>,
+}
+
+impl ConcurrentMerkleTree {
+    /// Prefix values used to generate a PDA for this account.
+    ///
+    /// Values are positional and appear in the following order:
+    ///
+    ///   0. `ConcurrentMerkleTree::PREFIX`
+    ///   1. payer (`Pubkey`)
+    ///   2. tree_id (`u64`)
+    pub const PREFIX: &'static [u8] = "concurrent_tree".as_bytes();
+
+    pub fn create_pda(
+        payer: Pubkey,
+        tree_id: u64,
+        bump: u8,
+    ) -> Result<solana_program::pubkey::Pubkey, solana_program::pubkey::PubkeyError> {
+        solana_program::pubkey::Pubkey::create_program_address(
+            &[
+                "concurrent_tree".as_bytes(),
+                payer.as_ref(),
+                tree_id.to_le_bytes().as_ref(),
+                &[bump],
+            ],
+            &crate::MERKLE_TREE_STORAGE_ID,
+        )
+    }
+
+    pub fn find_pda(payer: &Pubkey, tree_id: u64) -> (solana_program::pubkey::Pubkey, u8) {
+        solana_program::pubkey::Pubkey::find_program_address(
+            &[
+                "concurrent_tree".as_bytes(),
+                payer.as_ref(),
+                tree_id.to_le_bytes().as_ref(),
+            ],
+            &crate::MERKLE_TREE_STORAGE_ID,
+        )
+    }
+
+    #[inline(always)]
+    pub fn from_bytes(data: &[u8]) -> Result<Self, std::io::Error> {
+        let mut data = data;
+        Self::deserialize(&mut data)
+    }
+}
+
+impl<'a> TryFrom<&solana_program::account_info::AccountInfo<'a>> for ConcurrentMerkleTree {
+    type Error = std::io::Error;
+
+    fn try_from(
+        account_info: &solana_program::account_info::AccountInfo<'a>,
+    ) -> Result<Self, Self::Error> {
+        let mut data: &[u8] = &(*account_info.data).borrow();
+        Self::deserialize(&mut data)
+    }
+}
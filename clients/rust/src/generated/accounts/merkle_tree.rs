@@ -5,22 +5,81 @@
 //! [https://github.com/metaplex-foundation/kinobi]
 //!
 
+use crate::generated::types::HashKind;
 #[cfg(feature = "anchor")]
 use anchor_lang::prelude::{AnchorDeserialize, AnchorSerialize};
 #[cfg(not(feature = "anchor"))]
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(not(feature = "anchor"), derive(BorshSerialize, BorshDeserialize))]
 #[cfg_attr(feature = "anchor", derive(AnchorSerialize, AnchorDeserialize))]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct MerkleTree {
-    pub nodes: Vec<[u8; 32]>,
     pub max_depth: u8,
-    pub next_leaf_index: u8,
+    pub next_leaf_index: u32,
+    pub allow_leaf_reuse: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "serde_with::As::<serde_with::DisplayFromStr>")
+    )]
+    pub authority: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "serde_with::As::<serde_with::DisplayFromStr>")
+    )]
+    pub pending_authority: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "serde_with::As::<[serde_with::DisplayFromStr; 8]>")
+    )]
+    pub delegates: [Pubkey; 8],
+    pub active_leaf_count: u32,
+    pub hash_kind: HashKind,
+    pub domain_separated: bool,
+    pub root_history_index: u32,
+    pub root_history: Vec<[u8; 32]>,
+    pub nodes: Vec<[u8; 32]>,
 }
 
 impl MerkleTree {
+    /// Prefix values used to generate a PDA for this account.
+    ///
+    /// Values are positional and appear in the following order:
+    ///
+    ///   0. `MerkleTree::PREFIX`
+    ///   1. payer (`Pubkey`)
+    ///   2. tree_id (`u64`)
+    pub const PREFIX: &'static [u8] = "tree".as_bytes();
+
+    pub fn create_pda(
+        payer: Pubkey,
+        tree_id: u64,
+        bump: u8,
+    ) -> Result<solana_program::pubkey::Pubkey, solana_program::pubkey::PubkeyError> {
+        solana_program::pubkey::Pubkey::create_program_address(
+            &[
+                "tree".as_bytes(),
+                payer.as_ref(),
+                tree_id.to_le_bytes().as_ref(),
+                &[bump],
+            ],
+            &crate::MERKLE_TREE_STORAGE_ID,
+        )
+    }
+
+    pub fn find_pda(payer: &Pubkey, tree_id: u64) -> (solana_program::pubkey::Pubkey, u8) {
+        solana_program::pubkey::Pubkey::find_program_address(
+            &[
+                "tree".as_bytes(),
+                payer.as_ref(),
+                tree_id.to_le_bytes().as_ref(),
+            ],
+            &crate::MERKLE_TREE_STORAGE_ID,
+        )
+    }
+
     #[inline(always)]
     pub fn from_bytes(data: &[u8]) -> Result<Self, std::io::Error> {
         let mut data = data;
@@ -5,6 +5,10 @@
 //! [https://github.com/metaplex-foundation/kinobi]
 //!
 
+pub(crate) mod r#concurrent_merkle_tree;
+pub(crate) mod r#incremental_merkle_tree;
 pub(crate) mod r#merkle_tree;
 
+pub use self::r#concurrent_merkle_tree::*;
+pub use self::r#incremental_merkle_tree::*;
 pub use self::r#merkle_tree::*;
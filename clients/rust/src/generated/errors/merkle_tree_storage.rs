@@ -28,6 +28,45 @@ pub enum MerkleTreeStorageError {
     /// 5 (0x5) - Payer must be a signer
     #[error("Payer must be a signer")]
     PayerMustBeSigner,
+    /// 6 (0x6) - Invalid max depth
+    #[error("Invalid max depth")]
+    InvalidMaxDepth,
+    /// 7 (0x7) - Invalid proof
+    #[error("Invalid proof")]
+    InvalidProof,
+    /// 8 (0x8) - Leaf index out of bounds
+    #[error("Leaf index out of bounds")]
+    LeafIndexOutOfBounds,
+    /// 9 (0x9) - Leaf does not match
+    #[error("Leaf does not match")]
+    LeafMismatch,
+    /// 10 (0xA) - Invalid leaf
+    #[error("Invalid leaf")]
+    InvalidLeaf,
+    /// 11 (0xB) - Leaf was removed
+    #[error("Leaf was removed")]
+    LeafRemoved,
+    /// 12 (0xC) - Signer is not allowed to modify the tree
+    #[error("Signer is not allowed to modify the tree")]
+    Unauthorized,
+    /// 13 (0xD) - Delegate list is full
+    #[error("Delegate list is full")]
+    DelegateListFull,
+    /// 14 (0xE) - Delegate not found
+    #[error("Delegate not found")]
+    DelegateNotFound,
+    /// 15 (0xF) - Tree is not empty
+    #[error("Tree is not empty")]
+    TreeNotEmpty,
+    /// 16 (0x10) - Invalid root history size
+    #[error("Invalid root history size")]
+    InvalidRootHistorySize,
+    /// 17 (0x11) - Invalid canopy depth
+    #[error("Invalid canopy depth")]
+    InvalidCanopyDepth,
+    /// 18 (0x12) - Invalid change log size
+    #[error("Invalid change log size")]
+    InvalidChangeLogSize,
 }
 
 impl solana_program::program_error::PrintProgramError for MerkleTreeStorageError {
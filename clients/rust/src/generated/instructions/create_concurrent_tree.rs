@@ -0,0 +1,535 @@
+//! This code was AUTOGENERATED using the kinobi library.
+//! Please DO NOT EDIT THIS FILE, instead use visitors
+//! to add features, then rerun kinobi to update it.
+//!
+//! [https://github.com/metaplex-foundation/kinobi]
+//!
+
+use crate::generated::types::HashKind;
+#[cfg(feature = "anchor")]
+use anchor_lang::prelude::{AnchorDeserialize, AnchorSerialize};
+#[cfg(not(feature = "anchor"))]
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Accounts.
+pub struct CreateConcurrentTree {
+    /// The account paying for the storage fees
+    pub payer: solana_program::pubkey::Pubkey,
+    /// The address of the new account
+    pub tree: solana_program::pubkey::Pubkey,
+    /// The system program
+    pub system_program: solana_program::pubkey::Pubkey,
+}
+
+impl CreateConcurrentTree {
+    pub fn instruction(
+        &self,
+        args: CreateConcurrentTreeInstructionArgs,
+    ) -> solana_program::instruction::Instruction {
+        self.instruction_with_remaining_accounts(args, &[])
+    }
+    #[allow(clippy::vec_init_then_push)]
+    pub fn instruction_with_remaining_accounts(
+        &self,
+        args: CreateConcurrentTreeInstructionArgs,
+        remaining_accounts: &[solana_program::instruction::AccountMeta],
+    ) -> solana_program::instruction::Instruction {
+        let mut accounts = Vec::with_capacity(3 + remaining_accounts.len());
+        accounts.push(solana_program::instruction::AccountMeta::new(
+            self.payer, true,
+        ));
+        accounts.push(solana_program::instruction::AccountMeta::new(
+            self.tree, false,
+        ));
+        accounts.push(solana_program::instruction::AccountMeta::new_readonly(
+            self.system_program,
+            false,
+        ));
+        accounts.extend_from_slice(remaining_accounts);
+        let mut data = CreateConcurrentTreeInstructionData::new()
+            .try_to_vec()
+            .unwrap();
+        let mut args = args.try_to_vec().unwrap();
+        data.append(&mut args);
+
+        solana_program::instruction::Instruction {
+            program_id: crate::MERKLE_TREE_STORAGE_ID,
+            accounts,
+            data,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "anchor"), derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "anchor", derive(AnchorSerialize, AnchorDeserialize))]
+pub struct CreateConcurrentTreeInstructionData {
+    discriminator: u8,
+}
+
+impl CreateConcurrentTreeInstructionData {
+    pub fn new() -> Self {
+        Self { discriminator: 14 }
+    }
+}
+
+#[cfg_attr(not(feature = "anchor"), derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "anchor", derive(AnchorSerialize, AnchorDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CreateConcurrentTreeInstructionArgs {
+    pub max_depth: u8,
+    pub tree_id: u64,
+    pub change_log_size: u8,
+    pub authority: Option<Pubkey>,
+    pub hash_kind: HashKind,
+    pub domain_separated: bool,
+    pub canopy_depth: u8,
+}
+
+/// Instruction builder for `CreateConcurrentTree`.
+///
+/// ### Accounts:
+///
+///   0. `[writable, signer]` payer
+///   1. `[writable]` tree
+///   2. `[optional]` system_program (default to `11111111111111111111111111111111`)
+#[derive(Default)]
+pub struct CreateConcurrentTreeBuilder {
+    payer: Option<solana_program::pubkey::Pubkey>,
+    tree: Option<solana_program::pubkey::Pubkey>,
+    system_program: Option<solana_program::pubkey::Pubkey>,
+    max_depth: Option<u8>,
+    tree_id: Option<u64>,
+    change_log_size: Option<u8>,
+    authority: Option<Pubkey>,
+    hash_kind: Option<HashKind>,
+    domain_separated: Option<bool>,
+    canopy_depth: Option<u8>,
+    __remaining_accounts: Vec<solana_program::instruction::AccountMeta>,
+}
+
+impl CreateConcurrentTreeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The account paying for the storage fees
+    #[inline(always)]
+    pub fn payer(&mut self, payer: solana_program::pubkey::Pubkey) -> &mut Self {
+        self.payer = Some(payer);
+        self
+    }
+    /// The address of the new account
+    #[inline(always)]
+    pub fn tree(&mut self, tree: solana_program::pubkey::Pubkey) -> &mut Self {
+        self.tree = Some(tree);
+        self
+    }
+    /// `[optional account, default to '11111111111111111111111111111111']`
+    /// The system program
+    #[inline(always)]
+    pub fn system_program(&mut self, system_program: solana_program::pubkey::Pubkey) -> &mut Self {
+        self.system_program = Some(system_program);
+        self
+    }
+    #[inline(always)]
+    pub fn max_depth(&mut self, max_depth: u8) -> &mut Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+    #[inline(always)]
+    pub fn tree_id(&mut self, tree_id: u64) -> &mut Self {
+        self.tree_id = Some(tree_id);
+        self
+    }
+    #[inline(always)]
+    pub fn change_log_size(&mut self, change_log_size: u8) -> &mut Self {
+        self.change_log_size = Some(change_log_size);
+        self
+    }
+    /// `[optional argument]`
+    #[inline(always)]
+    pub fn authority(&mut self, authority: Pubkey) -> &mut Self {
+        self.authority = Some(authority);
+        self
+    }
+    #[inline(always)]
+    pub fn hash_kind(&mut self, hash_kind: HashKind) -> &mut Self {
+        self.hash_kind = Some(hash_kind);
+        self
+    }
+    #[inline(always)]
+    pub fn domain_separated(&mut self, domain_separated: bool) -> &mut Self {
+        self.domain_separated = Some(domain_separated);
+        self
+    }
+    #[inline(always)]
+    pub fn canopy_depth(&mut self, canopy_depth: u8) -> &mut Self {
+        self.canopy_depth = Some(canopy_depth);
+        self
+    }
+    /// Add an aditional account to the instruction.
+    #[inline(always)]
+    pub fn add_remaining_account(
+        &mut self,
+        account: solana_program::instruction::AccountMeta,
+    ) -> &mut Self {
+        self.__remaining_accounts.push(account);
+        self
+    }
+    /// Add additional accounts to the instruction.
+    #[inline(always)]
+    pub fn add_remaining_accounts(
+        &mut self,
+        accounts: &[solana_program::instruction::AccountMeta],
+    ) -> &mut Self {
+        self.__remaining_accounts.extend_from_slice(accounts);
+        self
+    }
+    #[allow(clippy::clone_on_copy)]
+    pub fn instruction(&self) -> solana_program::instruction::Instruction {
+        let accounts = CreateConcurrentTree {
+            payer: self.payer.expect("payer is not set"),
+            tree: self.tree.expect("tree is not set"),
+            system_program: self
+                .system_program
+                .unwrap_or(solana_program::pubkey!("11111111111111111111111111111111")),
+        };
+        let args = CreateConcurrentTreeInstructionArgs {
+            max_depth: self.max_depth.clone().expect("max_depth is not set"),
+            tree_id: self.tree_id.clone().expect("tree_id is not set"),
+            change_log_size: self
+                .change_log_size
+                .clone()
+                .expect("change_log_size is not set"),
+            authority: self.authority.clone(),
+            hash_kind: self.hash_kind.clone().expect("hash_kind is not set"),
+            domain_separated: self
+                .domain_separated
+                .clone()
+                .expect("domain_separated is not set"),
+            canopy_depth: self.canopy_depth.clone().expect("canopy_depth is not set"),
+        };
+
+        accounts.instruction_with_remaining_accounts(args, &self.__remaining_accounts)
+    }
+}
+
+/// `create_concurrent_tree` CPI accounts.
+pub struct CreateConcurrentTreeCpiAccounts<'a, 'b> {
+    /// The account paying for the storage fees
+    pub payer: &'b solana_program::account_info::AccountInfo<'a>,
+    /// The address of the new account
+    pub tree: &'b solana_program::account_info::AccountInfo<'a>,
+    /// The system program
+    pub system_program: &'b solana_program::account_info::AccountInfo<'a>,
+}
+
+/// `create_concurrent_tree` CPI instruction.
+pub struct CreateConcurrentTreeCpi<'a, 'b> {
+    /// The program to invoke.
+    pub __program: &'b solana_program::account_info::AccountInfo<'a>,
+    /// The account paying for the storage fees
+    pub payer: &'b solana_program::account_info::AccountInfo<'a>,
+    /// The address of the new account
+    pub tree: &'b solana_program::account_info::AccountInfo<'a>,
+    /// The system program
+    pub system_program: &'b solana_program::account_info::AccountInfo<'a>,
+    /// The arguments for the instruction.
+    pub __args: CreateConcurrentTreeInstructionArgs,
+}
+
+impl<'a, 'b> CreateConcurrentTreeCpi<'a, 'b> {
+    pub fn new(
+        program: &'b solana_program::account_info::AccountInfo<'a>,
+        accounts: CreateConcurrentTreeCpiAccounts<'a, 'b>,
+        args: CreateConcurrentTreeInstructionArgs,
+    ) -> Self {
+        Self {
+            __program: program,
+            payer: accounts.payer,
+            tree: accounts.tree,
+            system_program: accounts.system_program,
+            __args: args,
+        }
+    }
+    #[inline(always)]
+    pub fn invoke(&self) -> solana_program::entrypoint::ProgramResult {
+        self.invoke_signed_with_remaining_accounts(&[], &[])
+    }
+    #[inline(always)]
+    pub fn invoke_with_remaining_accounts(
+        &self,
+        remaining_accounts: &[(
+            &'b solana_program::account_info::AccountInfo<'a>,
+            bool,
+            bool,
+        )],
+    ) -> solana_program::entrypoint::ProgramResult {
+        self.invoke_signed_with_remaining_accounts(&[], remaining_accounts)
+    }
+    #[inline(always)]
+    pub fn invoke_signed(
+        &self,
+        signers_seeds: &[&[&[u8]]],
+    ) -> solana_program::entrypoint::ProgramResult {
+        self.invoke_signed_with_remaining_accounts(signers_seeds, &[])
+    }
+    #[allow(clippy::clone_on_copy)]
+    #[allow(clippy::vec_init_then_push)]
+    pub fn invoke_signed_with_remaining_accounts(
+        &self,
+        signers_seeds: &[&[&[u8]]],
+        remaining_accounts: &[(
+            &'b solana_program::account_info::AccountInfo<'a>,
+            bool,
+            bool,
+        )],
+    ) -> solana_program::entrypoint::ProgramResult {
+        let mut accounts = Vec::with_capacity(3 + remaining_accounts.len());
+        accounts.push(solana_program::instruction::AccountMeta::new(
+            *self.payer.key,
+            true,
+        ));
+        accounts.push(solana_program::instruction::AccountMeta::new(
+            *self.tree.key,
+            false,
+        ));
+        accounts.push(solana_program::instruction::AccountMeta::new_readonly(
+            *self.system_program.key,
+            false,
+        ));
+        remaining_accounts.iter().for_each(|remaining_account| {
+            accounts.push(solana_program::instruction::AccountMeta {
+                pubkey: *remaining_account.0.key,
+                is_signer: remaining_account.1,
+                is_writable: remaining_account.2,
+            })
+        });
+        let mut data = CreateConcurrentTreeInstructionData::new()
+            .try_to_vec()
+            .unwrap();
+        let mut args = self.__args.try_to_vec().unwrap();
+        data.append(&mut args);
+
+        let instruction = solana_program::instruction::Instruction {
+            program_id: crate::MERKLE_TREE_STORAGE_ID,
+            accounts,
+            data,
+        };
+        let mut account_infos = Vec::with_capacity(3 + 1 + remaining_accounts.len());
+        account_infos.push(self.__program.clone());
+        account_infos.push(self.payer.clone());
+        account_infos.push(self.tree.clone());
+        account_infos.push(self.system_program.clone());
+        remaining_accounts
+            .iter()
+            .for_each(|remaining_account| account_infos.push(remaining_account.0.clone()));
+
+        if signers_seeds.is_empty() {
+            solana_program::program::invoke(&instruction, &account_infos)
+        } else {
+            solana_program::program::invoke_signed(&instruction, &account_infos, signers_seeds)
+        }
+    }
+}
+
+/// Instruction builder for `CreateConcurrentTree` via CPI.
+///
+/// ### Accounts:
+///
+///   0. `[writable, signer]` payer
+///   1. `[writable]` tree
+///   2. `[]` system_program
+pub struct CreateConcurrentTreeCpiBuilder<'a, 'b> {
+    instruction: Box<CreateConcurrentTreeCpiBuilderInstruction<'a, 'b>>,
+}
+
+impl<'a, 'b> CreateConcurrentTreeCpiBuilder<'a, 'b> {
+    pub fn new(program: &'b solana_program::account_info::AccountInfo<'a>) -> Self {
+        let instruction = Box::new(CreateConcurrentTreeCpiBuilderInstruction {
+            __program: program,
+            payer: None,
+            tree: None,
+            system_program: None,
+            max_depth: None,
+            tree_id: None,
+            change_log_size: None,
+            authority: None,
+            hash_kind: None,
+            domain_separated: None,
+            canopy_depth: None,
+            __remaining_accounts: Vec::new(),
+        });
+        Self { instruction }
+    }
+    /// The account paying for the storage fees
+    #[inline(always)]
+    pub fn payer(&mut self, payer: &'b solana_program::account_info::AccountInfo<'a>) -> &mut Self {
+        self.instruction.payer = Some(payer);
+        self
+    }
+    /// The address of the new account
+    #[inline(always)]
+    pub fn tree(&mut self, tree: &'b solana_program::account_info::AccountInfo<'a>) -> &mut Self {
+        self.instruction.tree = Some(tree);
+        self
+    }
+    /// The system program
+    #[inline(always)]
+    pub fn system_program(
+        &mut self,
+        system_program: &'b solana_program::account_info::AccountInfo<'a>,
+    ) -> &mut Self {
+        self.instruction.system_program = Some(system_program);
+        self
+    }
+    #[inline(always)]
+    pub fn max_depth(&mut self, max_depth: u8) -> &mut Self {
+        self.instruction.max_depth = Some(max_depth);
+        self
+    }
+    #[inline(always)]
+    pub fn tree_id(&mut self, tree_id: u64) -> &mut Self {
+        self.instruction.tree_id = Some(tree_id);
+        self
+    }
+    #[inline(always)]
+    pub fn change_log_size(&mut self, change_log_size: u8) -> &mut Self {
+        self.instruction.change_log_size = Some(change_log_size);
+        self
+    }
+    /// `[optional argument]`
+    #[inline(always)]
+    pub fn authority(&mut self, authority: Pubkey) -> &mut Self {
+        self.instruction.authority = Some(authority);
+        self
+    }
+    #[inline(always)]
+    pub fn hash_kind(&mut self, hash_kind: HashKind) -> &mut Self {
+        self.instruction.hash_kind = Some(hash_kind);
+        self
+    }
+    #[inline(always)]
+    pub fn domain_separated(&mut self, domain_separated: bool) -> &mut Self {
+        self.instruction.domain_separated = Some(domain_separated);
+        self
+    }
+    #[inline(always)]
+    pub fn canopy_depth(&mut self, canopy_depth: u8) -> &mut Self {
+        self.instruction.canopy_depth = Some(canopy_depth);
+        self
+    }
+    /// Add an additional account to the instruction.
+    #[inline(always)]
+    pub fn add_remaining_account(
+        &mut self,
+        account: &'b solana_program::account_info::AccountInfo<'a>,
+        is_writable: bool,
+        is_signer: bool,
+    ) -> &mut Self {
+        self.instruction
+            .__remaining_accounts
+            .push((account, is_writable, is_signer));
+        self
+    }
+    /// Add additional accounts to the instruction.
+    ///
+    /// Each account is represented by a tuple of the `AccountInfo`, a `bool` indicating whether the account is writable or not,
+    /// and a `bool` indicating whether the account is a signer or not.
+    #[inline(always)]
+    pub fn add_remaining_accounts(
+        &mut self,
+        accounts: &[(
+            &'b solana_program::account_info::AccountInfo<'a>,
+            bool,
+            bool,
+        )],
+    ) -> &mut Self {
+        self.instruction
+            .__remaining_accounts
+            .extend_from_slice(accounts);
+        self
+    }
+    #[inline(always)]
+    pub fn invoke(&self) -> solana_program::entrypoint::ProgramResult {
+        self.invoke_signed(&[])
+    }
+    #[allow(clippy::clone_on_copy)]
+    #[allow(clippy::vec_init_then_push)]
+    pub fn invoke_signed(
+        &self,
+        signers_seeds: &[&[&[u8]]],
+    ) -> solana_program::entrypoint::ProgramResult {
+        let args = CreateConcurrentTreeInstructionArgs {
+            max_depth: self
+                .instruction
+                .max_depth
+                .clone()
+                .expect("max_depth is not set"),
+            tree_id: self
+                .instruction
+                .tree_id
+                .clone()
+                .expect("tree_id is not set"),
+            change_log_size: self
+                .instruction
+                .change_log_size
+                .clone()
+                .expect("change_log_size is not set"),
+            authority: self.instruction.authority.clone(),
+            hash_kind: self
+                .instruction
+                .hash_kind
+                .clone()
+                .expect("hash_kind is not set"),
+            domain_separated: self
+                .instruction
+                .domain_separated
+                .clone()
+                .expect("domain_separated is not set"),
+            canopy_depth: self
+                .instruction
+                .canopy_depth
+                .clone()
+                .expect("canopy_depth is not set"),
+        };
+        let instruction = CreateConcurrentTreeCpi {
+            __program: self.instruction.__program,
+
+            payer: self.instruction.payer.expect("payer is not set"),
+
+            tree: self.instruction.tree.expect("tree is not set"),
+
+            system_program: self
+                .instruction
+                .system_program
+                .expect("system_program is not set"),
+            __args: args,
+        };
+        instruction.invoke_signed_with_remaining_accounts(
+            signers_seeds,
+            &self.instruction.__remaining_accounts,
+        )
+    }
+}
+
+struct CreateConcurrentTreeCpiBuilderInstruction<'a, 'b> {
+    __program: &'b solana_program::account_info::AccountInfo<'a>,
+    payer: Option<&'b solana_program::account_info::AccountInfo<'a>>,
+    tree: Option<&'b solana_program::account_info::AccountInfo<'a>>,
+    system_program: Option<&'b solana_program::account_info::AccountInfo<'a>>,
+    max_depth: Option<u8>,
+    tree_id: Option<u64>,
+    change_log_size: Option<u8>,
+    authority: Option<Pubkey>,
+    hash_kind: Option<HashKind>,
+    domain_separated: Option<bool>,
+    canopy_depth: Option<u8>,
+    /// Additional instruction accounts `(AccountInfo, is_writable, is_signer)`.
+    __remaining_accounts: Vec<(
+        &'b solana_program::account_info::AccountInfo<'a>,
+        bool,
+        bool,
+    )>,
+}
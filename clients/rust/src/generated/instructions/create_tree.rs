@@ -5,10 +5,12 @@
 //! [https://github.com/metaplex-foundation/kinobi]
 //!
 
+use crate::generated::types::HashKind;
 #[cfg(feature = "anchor")]
 use anchor_lang::prelude::{AnchorDeserialize, AnchorSerialize};
 #[cfg(not(feature = "anchor"))]
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
 
 /// Accounts.
 pub struct CreateTree {
@@ -81,6 +83,12 @@ impl CreateTreeInstructionData {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct CreateTreeInstructionArgs {
     pub max_depth: u8,
+    pub tree_id: u64,
+    pub allow_leaf_reuse: bool,
+    pub authority: Option<Pubkey>,
+    pub hash_kind: HashKind,
+    pub domain_separated: bool,
+    pub root_history_size: u8,
 }
 
 /// Instruction builder for `CreateTree`.
@@ -98,6 +106,12 @@ pub struct CreateTreeBuilder {
     system_program: Option<solana_program::pubkey::Pubkey>,
     sysvar_rent: Option<solana_program::pubkey::Pubkey>,
     max_depth: Option<u8>,
+    tree_id: Option<u64>,
+    allow_leaf_reuse: Option<bool>,
+    authority: Option<Pubkey>,
+    hash_kind: Option<HashKind>,
+    domain_separated: Option<bool>,
+    root_history_size: Option<u8>,
     __remaining_accounts: Vec<solana_program::instruction::AccountMeta>,
 }
 
@@ -136,6 +150,37 @@ impl CreateTreeBuilder {
         self.max_depth = Some(max_depth);
         self
     }
+    #[inline(always)]
+    pub fn tree_id(&mut self, tree_id: u64) -> &mut Self {
+        self.tree_id = Some(tree_id);
+        self
+    }
+    #[inline(always)]
+    pub fn allow_leaf_reuse(&mut self, allow_leaf_reuse: bool) -> &mut Self {
+        self.allow_leaf_reuse = Some(allow_leaf_reuse);
+        self
+    }
+    /// `[optional argument]`
+    #[inline(always)]
+    pub fn authority(&mut self, authority: Pubkey) -> &mut Self {
+        self.authority = Some(authority);
+        self
+    }
+    #[inline(always)]
+    pub fn hash_kind(&mut self, hash_kind: HashKind) -> &mut Self {
+        self.hash_kind = Some(hash_kind);
+        self
+    }
+    #[inline(always)]
+    pub fn domain_separated(&mut self, domain_separated: bool) -> &mut Self {
+        self.domain_separated = Some(domain_separated);
+        self
+    }
+    #[inline(always)]
+    pub fn root_history_size(&mut self, root_history_size: u8) -> &mut Self {
+        self.root_history_size = Some(root_history_size);
+        self
+    }
     /// Add an aditional account to the instruction.
     #[inline(always)]
     pub fn add_remaining_account(
@@ -168,6 +213,21 @@ impl CreateTreeBuilder {
         };
         let args = CreateTreeInstructionArgs {
             max_depth: self.max_depth.clone().expect("max_depth is not set"),
+            tree_id: self.tree_id.clone().expect("tree_id is not set"),
+            allow_leaf_reuse: self
+                .allow_leaf_reuse
+                .clone()
+                .expect("allow_leaf_reuse is not set"),
+            authority: self.authority.clone(),
+            hash_kind: self.hash_kind.clone().expect("hash_kind is not set"),
+            domain_separated: self
+                .domain_separated
+                .clone()
+                .expect("domain_separated is not set"),
+            root_history_size: self
+                .root_history_size
+                .clone()
+                .expect("root_history_size is not set"),
         };
 
         accounts.instruction_with_remaining_accounts(args, &self.__remaining_accounts)
@@ -322,6 +382,12 @@ impl<'a, 'b> CreateTreeCpiBuilder<'a, 'b> {
             system_program: None,
             sysvar_rent: None,
             max_depth: None,
+            tree_id: None,
+            allow_leaf_reuse: None,
+            authority: None,
+            hash_kind: None,
+            domain_separated: None,
+            root_history_size: None,
             __remaining_accounts: Vec::new(),
         });
         Self { instruction }
@@ -361,6 +427,37 @@ impl<'a, 'b> CreateTreeCpiBuilder<'a, 'b> {
         self.instruction.max_depth = Some(max_depth);
         self
     }
+    #[inline(always)]
+    pub fn tree_id(&mut self, tree_id: u64) -> &mut Self {
+        self.instruction.tree_id = Some(tree_id);
+        self
+    }
+    #[inline(always)]
+    pub fn allow_leaf_reuse(&mut self, allow_leaf_reuse: bool) -> &mut Self {
+        self.instruction.allow_leaf_reuse = Some(allow_leaf_reuse);
+        self
+    }
+    /// `[optional argument]`
+    #[inline(always)]
+    pub fn authority(&mut self, authority: Pubkey) -> &mut Self {
+        self.instruction.authority = Some(authority);
+        self
+    }
+    #[inline(always)]
+    pub fn hash_kind(&mut self, hash_kind: HashKind) -> &mut Self {
+        self.instruction.hash_kind = Some(hash_kind);
+        self
+    }
+    #[inline(always)]
+    pub fn domain_separated(&mut self, domain_separated: bool) -> &mut Self {
+        self.instruction.domain_separated = Some(domain_separated);
+        self
+    }
+    #[inline(always)]
+    pub fn root_history_size(&mut self, root_history_size: u8) -> &mut Self {
+        self.instruction.root_history_size = Some(root_history_size);
+        self
+    }
     /// Add an additional account to the instruction.
     #[inline(always)]
     pub fn add_remaining_account(
@@ -408,6 +505,32 @@ impl<'a, 'b> CreateTreeCpiBuilder<'a, 'b> {
                 .max_depth
                 .clone()
                 .expect("max_depth is not set"),
+            tree_id: self
+                .instruction
+                .tree_id
+                .clone()
+                .expect("tree_id is not set"),
+            allow_leaf_reuse: self
+                .instruction
+                .allow_leaf_reuse
+                .clone()
+                .expect("allow_leaf_reuse is not set"),
+            authority: self.instruction.authority.clone(),
+            hash_kind: self
+                .instruction
+                .hash_kind
+                .clone()
+                .expect("hash_kind is not set"),
+            domain_separated: self
+                .instruction
+                .domain_separated
+                .clone()
+                .expect("domain_separated is not set"),
+            root_history_size: self
+                .instruction
+                .root_history_size
+                .clone()
+                .expect("root_history_size is not set"),
         };
         let instruction = CreateTreeCpi {
             __program: self.instruction.__program,
@@ -441,6 +564,12 @@ struct CreateTreeCpiBuilderInstruction<'a, 'b> {
     system_program: Option<&'b solana_program::account_info::AccountInfo<'a>>,
     sysvar_rent: Option<&'b solana_program::account_info::AccountInfo<'a>>,
     max_depth: Option<u8>,
+    tree_id: Option<u64>,
+    allow_leaf_reuse: Option<bool>,
+    authority: Option<Pubkey>,
+    hash_kind: Option<HashKind>,
+    domain_separated: Option<bool>,
+    root_history_size: Option<u8>,
     /// Additional instruction accounts `(AccountInfo, is_writable, is_signer)`.
     __remaining_accounts: Vec<(
         &'b solana_program::account_info::AccountInfo<'a>,
@@ -12,9 +12,9 @@ use borsh::{BorshDeserialize, BorshSerialize};
 
 /// Accounts.
 pub struct InsertLeaf {
-    /// The account paying for the storage fees
-    pub payer: solana_program::pubkey::Pubkey,
-    /// The address of the new account
+    /// The tree authority or one of its delegates
+    pub authority: solana_program::pubkey::Pubkey,
+    /// The tree to append the leaf to
     pub tree: solana_program::pubkey::Pubkey,
 }
 
@@ -32,8 +32,9 @@ impl InsertLeaf {
         remaining_accounts: &[solana_program::instruction::AccountMeta],
     ) -> solana_program::instruction::Instruction {
         let mut accounts = Vec::with_capacity(2 + remaining_accounts.len());
-        accounts.push(solana_program::instruction::AccountMeta::new(
-            self.payer, true,
+        accounts.push(solana_program::instruction::AccountMeta::new_readonly(
+            self.authority,
+            true,
         ));
         accounts.push(solana_program::instruction::AccountMeta::new(
             self.tree, false,
@@ -75,11 +76,11 @@ pub struct InsertLeafInstructionArgs {
 ///
 /// ### Accounts:
 ///
-///   0. `[writable, signer]` payer
+///   0. `[signer]` authority
 ///   1. `[writable]` tree
 #[derive(Default)]
 pub struct InsertLeafBuilder {
-    payer: Option<solana_program::pubkey::Pubkey>,
+    authority: Option<solana_program::pubkey::Pubkey>,
     tree: Option<solana_program::pubkey::Pubkey>,
     leaf: Option<[u8; 32]>,
     __remaining_accounts: Vec<solana_program::instruction::AccountMeta>,
@@ -89,13 +90,13 @@ impl InsertLeafBuilder {
     pub fn new() -> Self {
         Self::default()
     }
-    /// The account paying for the storage fees
+    /// The tree authority or one of its delegates
     #[inline(always)]
-    pub fn payer(&mut self, payer: solana_program::pubkey::Pubkey) -> &mut Self {
-        self.payer = Some(payer);
+    pub fn authority(&mut self, authority: solana_program::pubkey::Pubkey) -> &mut Self {
+        self.authority = Some(authority);
         self
     }
-    /// The address of the new account
+    /// The tree to append the leaf to
     #[inline(always)]
     pub fn tree(&mut self, tree: solana_program::pubkey::Pubkey) -> &mut Self {
         self.tree = Some(tree);
@@ -127,7 +128,7 @@ impl InsertLeafBuilder {
     #[allow(clippy::clone_on_copy)]
     pub fn instruction(&self) -> solana_program::instruction::Instruction {
         let accounts = InsertLeaf {
-            payer: self.payer.expect("payer is not set"),
+            authority: self.authority.expect("authority is not set"),
             tree: self.tree.expect("tree is not set"),
         };
         let args = InsertLeafInstructionArgs {
@@ -140,9 +141,9 @@ impl InsertLeafBuilder {
 
 /// `insert_leaf` CPI accounts.
 pub struct InsertLeafCpiAccounts<'a, 'b> {
-    /// The account paying for the storage fees
-    pub payer: &'b solana_program::account_info::AccountInfo<'a>,
-    /// The address of the new account
+    /// The tree authority or one of its delegates
+    pub authority: &'b solana_program::account_info::AccountInfo<'a>,
+    /// The tree to append the leaf to
     pub tree: &'b solana_program::account_info::AccountInfo<'a>,
 }
 
@@ -150,9 +151,9 @@ pub struct InsertLeafCpiAccounts<'a, 'b> {
 pub struct InsertLeafCpi<'a, 'b> {
     /// The program to invoke.
     pub __program: &'b solana_program::account_info::AccountInfo<'a>,
-    /// The account paying for the storage fees
-    pub payer: &'b solana_program::account_info::AccountInfo<'a>,
-    /// The address of the new account
+    /// The tree authority or one of its delegates
+    pub authority: &'b solana_program::account_info::AccountInfo<'a>,
+    /// The tree to append the leaf to
     pub tree: &'b solana_program::account_info::AccountInfo<'a>,
     /// The arguments for the instruction.
     pub __args: InsertLeafInstructionArgs,
@@ -166,7 +167,7 @@ impl<'a, 'b> InsertLeafCpi<'a, 'b> {
     ) -> Self {
         Self {
             __program: program,
-            payer: accounts.payer,
+            authority: accounts.authority,
             tree: accounts.tree,
             __args: args,
         }
@@ -205,8 +206,8 @@ impl<'a, 'b> InsertLeafCpi<'a, 'b> {
         )],
     ) -> solana_program::entrypoint::ProgramResult {
         let mut accounts = Vec::with_capacity(2 + remaining_accounts.len());
-        accounts.push(solana_program::instruction::AccountMeta::new(
-            *self.payer.key,
+        accounts.push(solana_program::instruction::AccountMeta::new_readonly(
+            *self.authority.key,
             true,
         ));
         accounts.push(solana_program::instruction::AccountMeta::new(
@@ -231,7 +232,7 @@ impl<'a, 'b> InsertLeafCpi<'a, 'b> {
         };
         let mut account_infos = Vec::with_capacity(2 + 1 + remaining_accounts.len());
         account_infos.push(self.__program.clone());
-        account_infos.push(self.payer.clone());
+        account_infos.push(self.authority.clone());
         account_infos.push(self.tree.clone());
         remaining_accounts
             .iter()
@@ -249,7 +250,7 @@ impl<'a, 'b> InsertLeafCpi<'a, 'b> {
 ///
 /// ### Accounts:
 ///
-///   0. `[writable, signer]` payer
+///   0. `[signer]` authority
 ///   1. `[writable]` tree
 pub struct InsertLeafCpiBuilder<'a, 'b> {
     instruction: Box<InsertLeafCpiBuilderInstruction<'a, 'b>>,
@@ -259,20 +260,23 @@ impl<'a, 'b> InsertLeafCpiBuilder<'a, 'b> {
     pub fn new(program: &'b solana_program::account_info::AccountInfo<'a>) -> Self {
         let instruction = Box::new(InsertLeafCpiBuilderInstruction {
             __program: program,
-            payer: None,
+            authority: None,
             tree: None,
             leaf: None,
             __remaining_accounts: Vec::new(),
         });
         Self { instruction }
     }
-    /// The account paying for the storage fees
+    /// The tree authority or one of its delegates
     #[inline(always)]
-    pub fn payer(&mut self, payer: &'b solana_program::account_info::AccountInfo<'a>) -> &mut Self {
-        self.instruction.payer = Some(payer);
+    pub fn authority(
+        &mut self,
+        authority: &'b solana_program::account_info::AccountInfo<'a>,
+    ) -> &mut Self {
+        self.instruction.authority = Some(authority);
         self
     }
-    /// The address of the new account
+    /// The tree to append the leaf to
     #[inline(always)]
     pub fn tree(&mut self, tree: &'b solana_program::account_info::AccountInfo<'a>) -> &mut Self {
         self.instruction.tree = Some(tree);
@@ -330,7 +334,7 @@ impl<'a, 'b> InsertLeafCpiBuilder<'a, 'b> {
         let instruction = InsertLeafCpi {
             __program: self.instruction.__program,
 
-            payer: self.instruction.payer.expect("payer is not set"),
+            authority: self.instruction.authority.expect("authority is not set"),
 
             tree: self.instruction.tree.expect("tree is not set"),
             __args: args,
@@ -344,7 +348,7 @@ impl<'a, 'b> InsertLeafCpiBuilder<'a, 'b> {
 
 struct InsertLeafCpiBuilderInstruction<'a, 'b> {
     __program: &'b solana_program::account_info::AccountInfo<'a>,
-    payer: Option<&'b solana_program::account_info::AccountInfo<'a>>,
+    authority: Option<&'b solana_program::account_info::AccountInfo<'a>>,
     tree: Option<&'b solana_program::account_info::AccountInfo<'a>>,
     leaf: Option<[u8; 32]>,
     /// Additional instruction accounts `(AccountInfo, is_writable, is_signer)`.
@@ -5,8 +5,44 @@
 //! [https://github.com/metaplex-foundation/kinobi]
 //!
 
+pub(crate) mod r#accept_authority;
+pub(crate) mod r#add_delegate;
+pub(crate) mod r#append_concurrent_leaf;
+pub(crate) mod r#append_leaf;
+pub(crate) mod r#close_tree;
+pub(crate) mod r#create_concurrent_tree;
+pub(crate) mod r#create_incremental_tree;
 pub(crate) mod r#create_tree;
+pub(crate) mod r#emit_event;
+pub(crate) mod r#grow_tree;
 pub(crate) mod r#insert_leaf;
+pub(crate) mod r#insert_leaves;
+pub(crate) mod r#remove_delegate;
+pub(crate) mod r#remove_leaf;
+pub(crate) mod r#replace_concurrent_leaf;
+pub(crate) mod r#replace_leaf;
+pub(crate) mod r#set_authority;
+pub(crate) mod r#verify_concurrent_leaf;
+pub(crate) mod r#verify_leaf;
+pub(crate) mod r#verify_leaves;
 
+pub use self::r#accept_authority::*;
+pub use self::r#add_delegate::*;
+pub use self::r#append_concurrent_leaf::*;
+pub use self::r#append_leaf::*;
+pub use self::r#close_tree::*;
+pub use self::r#create_concurrent_tree::*;
+pub use self::r#create_incremental_tree::*;
 pub use self::r#create_tree::*;
+pub use self::r#emit_event::*;
+pub use self::r#grow_tree::*;
 pub use self::r#insert_leaf::*;
+pub use self::r#insert_leaves::*;
+pub use self::r#remove_delegate::*;
+pub use self::r#remove_leaf::*;
+pub use self::r#replace_concurrent_leaf::*;
+pub use self::r#replace_leaf::*;
+pub use self::r#set_authority::*;
+pub use self::r#verify_concurrent_leaf::*;
+pub use self::r#verify_leaf::*;
+pub use self::r#verify_leaves::*;
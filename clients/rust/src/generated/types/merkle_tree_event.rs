@@ -0,0 +1,40 @@
+//! This code was AUTOGENERATED using the kinobi library.
+//! Please DO NOT EDIT THIS FILE, instead use visitors
+//! to add features, then rerun kinobi to update it.
+//!
+//! [https://github.com/metaplex-foundation/kinobi]
+//!
+
+use crate::generated::types::AuthorityChanged;
+use crate::generated::types::AuthorityProposed;
+use crate::generated::types::DelegateAdded;
+use crate::generated::types::DelegateRemoved;
+use crate::generated::types::LeafInserted;
+use crate::generated::types::LeafRemoved;
+use crate::generated::types::LeafReplaced;
+use crate::generated::types::LeavesInserted;
+use crate::generated::types::TreeClosed;
+use crate::generated::types::TreeCreated;
+use crate::generated::types::TreeGrown;
+#[cfg(feature = "anchor")]
+use anchor_lang::prelude::{AnchorDeserialize, AnchorSerialize};
+#[cfg(not(feature = "anchor"))]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[cfg_attr(not(feature = "anchor"), derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "anchor", derive(AnchorSerialize, AnchorDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MerkleTreeEvent {
+    TreeCreated(TreeCreated),
+    LeafInserted(LeafInserted),
+    LeavesInserted(LeavesInserted),
+    LeafReplaced(LeafReplaced),
+    LeafRemoved(LeafRemoved),
+    AuthorityProposed(AuthorityProposed),
+    AuthorityChanged(AuthorityChanged),
+    DelegateAdded(DelegateAdded),
+    DelegateRemoved(DelegateRemoved),
+    TreeClosed(TreeClosed),
+    TreeGrown(TreeGrown),
+}
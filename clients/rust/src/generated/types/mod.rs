@@ -0,0 +1,38 @@
+//! This code was AUTOGENERATED using the kinobi library.
+//! Please DO NOT EDIT THIS FILE, instead use visitors
+//! to add features, then rerun kinobi to update it.
+//!
+//! [https://github.com/metaplex-foundation/kinobi]
+//!
+
+pub(crate) mod r#authority_changed;
+pub(crate) mod r#authority_proposed;
+pub(crate) mod r#change_log;
+pub(crate) mod r#delegate_added;
+pub(crate) mod r#delegate_removed;
+pub(crate) mod r#hash_kind;
+pub(crate) mod r#leaf_inserted;
+pub(crate) mod r#leaf_removed;
+pub(crate) mod r#leaf_replaced;
+pub(crate) mod r#leaves_inserted;
+pub(crate) mod r#merkle_tree_event;
+pub(crate) mod r#tree_closed;
+pub(crate) mod r#tree_created;
+pub(crate) mod r#tree_grown;
+pub(crate) mod r#tree_kind;
+
+pub use self::r#authority_changed::*;
+pub use self::r#authority_proposed::*;
+pub use self::r#change_log::*;
+pub use self::r#delegate_added::*;
+pub use self::r#delegate_removed::*;
+pub use self::r#hash_kind::*;
+pub use self::r#leaf_inserted::*;
+pub use self::r#leaf_removed::*;
+pub use self::r#leaf_replaced::*;
+pub use self::r#leaves_inserted::*;
+pub use self::r#merkle_tree_event::*;
+pub use self::r#tree_closed::*;
+pub use self::r#tree_created::*;
+pub use self::r#tree_grown::*;
+pub use self::r#tree_kind::*;
@@ -0,0 +1,20 @@
+pub use merkle_tree_storage_hasher::{
+    multiproof_positions, DomainSeparated, Hasher, Keccak256Hasher, PoseidonHasher, Sha256Hasher,
+    EMPTY_NODE, LEAF_PREFIX, NODE_PREFIX,
+};
+
+use crate::types::HashKind;
+
+/// Hashes data the way the program does for a tree created with this kind.
+impl Hasher for HashKind {
+    fn hashv(&self, data: &[&[u8]]) -> [u8; 32] {
+        match self {
+            HashKind::Sha256 => Sha256Hasher.hashv(data),
+            HashKind::Keccak256 => Keccak256Hasher.hashv(data),
+            HashKind::Poseidon => PoseidonHasher.hashv(data),
+        }
+    }
+}
+
+/// Hashing scheme of a tree account: its hash kind, domain-separated or not.
+pub type TreeHasher = merkle_tree_storage_hasher::TreeHasher<HashKind>;
@@ -1,4 +1,17 @@
+mod events;
 mod generated;
+mod hasher;
+mod merkle_proof;
+mod offchain_tree;
+mod proof;
+mod rs_merkle_interop;
+mod tree_account;
 
+pub use events::*;
 pub use generated::programs::MERKLE_TREE_STORAGE_ID as ID;
 pub use generated::*;
+pub use hasher::*;
+pub use merkle_proof::*;
+pub use offchain_tree::*;
+pub use proof::*;
+pub use rs_merkle_interop::*;
@@ -0,0 +1,260 @@
+use std::io::{Error, ErrorKind};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::types::HashKind;
+use crate::{verify_proof, Hasher, TreeHasher, EMPTY_NODE};
+
+/// Self-contained inclusion proof of `leaf` at `index` in a tree with the given
+/// `root`, checkable with [`MerkleProof::verify`] without access to the tree.
+///
+/// It travels as Borsh, as JSON with the `serde` feature (byte fields as hex, or
+/// base58 through [`MerkleProof::encoded`]), or in the compact form of
+/// [`MerkleProof::to_compact_bytes`].
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(try_from = "json::MerkleProofJson")
+)]
+pub struct MerkleProof {
+    /// Leaf value, as passed to InsertLeaf.
+    pub leaf: [u8; 32],
+    pub index: u32,
+    /// Depth of the tree, which a valid proof has one sibling per level of.
+    pub max_depth: u8,
+    /// Sibling path from the leaf level up.
+    pub siblings: Vec<[u8; 32]>,
+    pub root: [u8; 32],
+    pub hash_kind: HashKind,
+    pub domain_separated: bool,
+}
+
+impl MerkleProof {
+    pub fn hasher(&self) -> TreeHasher {
+        TreeHasher {
+            hash_kind: self.hash_kind,
+            domain_separated: self.domain_separated,
+        }
+    }
+
+    /// Checks that `siblings` link `leaf` at `index` to `root` with the tree's
+    /// hashing, through exactly `max_depth` levels (see [`verify_proof`]). As in
+    /// the program, the empty leaf value, which removed and unused slots hold, is
+    /// never proven. `root` and `max_depth` come with the proof, so compare them
+    /// with the tree's.
+    pub fn verify(&self) -> bool {
+        self.leaf != EMPTY_NODE
+            && verify_proof(
+                &self.hasher(),
+                &self.root,
+                &self.leaf,
+                self.index,
+                &self.siblings,
+                self.max_depth,
+            )
+    }
+
+    /// Compact binary form: a flags byte (bit 0 set for Keccak-256, bit 1 for a
+    /// domain-separated tree, bit 2 for Poseidon), `max_depth`, the index as little-endian `u32`,
+    /// leaf and root, then a bitmap of the siblings that are empty-subtree hashes,
+    /// which are left out, followed by the other siblings. Fails unless there is
+    /// one sibling per level.
+    pub fn to_compact_bytes(&self) -> std::io::Result<Vec<u8>> {
+        if self.siblings.len() != self.max_depth as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "proof does not have one sibling per level",
+            ));
+        }
+        let zero_hashes = self.hasher().zero_hashes(self.max_depth);
+        let mut flags = 0;
+        match self.hash_kind {
+            HashKind::Sha256 => {}
+            HashKind::Keccak256 => flags |= 1,
+            HashKind::Poseidon => flags |= 4,
+        }
+        if self.domain_separated {
+            flags |= 2;
+        }
+        let mut bitmap = vec![0u8; self.siblings.len().div_ceil(8)];
+        let mut siblings = Vec::new();
+        for (height, sibling) in self.siblings.iter().enumerate() {
+            if *sibling == zero_hashes[height] {
+                bitmap[height / 8] |= 1 << (height % 8);
+            } else {
+                siblings.extend_from_slice(sibling);
+            }
+        }
+
+        let mut bytes = vec![flags, self.max_depth];
+        bytes.extend_from_slice(&self.index.to_le_bytes());
+        bytes.extend_from_slice(&self.leaf);
+        bytes.extend_from_slice(&self.root);
+        bytes.extend_from_slice(&bitmap);
+        bytes.extend_from_slice(&siblings);
+        Ok(bytes)
+    }
+
+    /// Reads the form written by [`MerkleProof::to_compact_bytes`].
+    pub fn from_compact_bytes(bytes: &[u8]) -> std::io::Result<Self> {
+        let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message);
+        if bytes.len() < 70 {
+            return Err(invalid("proof is too short"));
+        }
+        let (header, mut rest) = bytes.split_at(70);
+        let flags = header[0];
+        let hash_kind = match flags & !2 {
+            0 => HashKind::Sha256,
+            1 => HashKind::Keccak256,
+            4 => HashKind::Poseidon,
+            _ => return Err(invalid("unknown proof flags")),
+        };
+        let domain_separated = flags & 2 != 0;
+        let max_depth = header[1];
+        let index = u32::from_le_bytes(header[2..6].try_into().unwrap());
+        let leaf = header[6..38].try_into().unwrap();
+        let root = header[38..70].try_into().unwrap();
+
+        let hasher = TreeHasher {
+            hash_kind,
+            domain_separated,
+        };
+        let zero_hashes = hasher.zero_hashes(max_depth);
+        let bitmap_len = (max_depth as usize).div_ceil(8);
+        if rest.len() < bitmap_len {
+            return Err(invalid("proof is too short"));
+        }
+        let bitmap;
+        (bitmap, rest) = rest.split_at(bitmap_len);
+        let mut siblings = Vec::with_capacity(max_depth as usize);
+        for height in 0..max_depth as usize {
+            if bitmap[height / 8] & (1 << (height % 8)) != 0 {
+                siblings.push(zero_hashes[height]);
+            } else {
+                if rest.len() < 32 {
+                    return Err(invalid("proof is too short"));
+                }
+                let sibling;
+                (sibling, rest) = rest.split_at(32);
+                siblings.push(sibling.try_into().unwrap());
+            }
+        }
+        if !rest.is_empty() {
+            return Err(invalid("trailing bytes after proof"));
+        }
+
+        Ok(Self {
+            leaf,
+            index,
+            max_depth,
+            siblings,
+            root,
+            hash_kind,
+            domain_separated,
+        })
+    }
+
+    /// The proof serializing its byte fields with `encoding`; `MerkleProof` itself
+    /// serializes them as hex, and deserializes either encoding.
+    #[cfg(feature = "serde")]
+    pub fn encoded(&self, encoding: ByteEncoding) -> json::EncodedMerkleProof<'_> {
+        json::EncodedMerkleProof {
+            proof: self,
+            encoding,
+        }
+    }
+}
+
+/// Text encoding of the byte fields of a [`MerkleProof`] in JSON.
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteEncoding {
+    Hex,
+    Base58,
+}
+
+#[cfg(feature = "serde")]
+pub mod json {
+    use serde::ser::SerializeStruct;
+    use serde::{Deserialize, Serialize, Serializer};
+
+    use super::{ByteEncoding, MerkleProof};
+    use crate::types::HashKind;
+
+    /// [`MerkleProof`] serializing its byte fields with the chosen encoding.
+    pub struct EncodedMerkleProof<'a> {
+        pub proof: &'a MerkleProof,
+        pub encoding: ByteEncoding,
+    }
+
+    impl Serialize for EncodedMerkleProof<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let encode = |bytes: &[u8; 32]| match self.encoding {
+                ByteEncoding::Hex => hex::encode(bytes),
+                ByteEncoding::Base58 => bs58::encode(bytes).into_string(),
+            };
+            let siblings: Vec<String> = self.proof.siblings.iter().map(encode).collect();
+            let mut state = serializer.serialize_struct("MerkleProof", 7)?;
+            state.serialize_field("leaf", &encode(&self.proof.leaf))?;
+            state.serialize_field("index", &self.proof.index)?;
+            state.serialize_field("max_depth", &self.proof.max_depth)?;
+            state.serialize_field("siblings", &siblings)?;
+            state.serialize_field("root", &encode(&self.proof.root))?;
+            state.serialize_field("hash_kind", &self.proof.hash_kind)?;
+            state.serialize_field("domain_separated", &self.proof.domain_separated)?;
+            state.end()
+        }
+    }
+
+    impl Serialize for MerkleProof {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.encoded(ByteEncoding::Hex).serialize(serializer)
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct MerkleProofJson {
+        leaf: String,
+        index: u32,
+        max_depth: u8,
+        siblings: Vec<String>,
+        root: String,
+        hash_kind: HashKind,
+        domain_separated: bool,
+    }
+
+    // 32 bytes are 64 characters in hex and at most 44 in base58
+    fn decode(text: &str) -> Result<[u8; 32], String> {
+        let bytes = if text.len() == 64 {
+            hex::decode(text).map_err(|error| error.to_string())?
+        } else {
+            bs58::decode(text)
+                .into_vec()
+                .map_err(|error| error.to_string())?
+        };
+        bytes
+            .try_into()
+            .map_err(|_| format!("{text} is not 32 bytes long"))
+    }
+
+    impl TryFrom<MerkleProofJson> for MerkleProof {
+        type Error = String;
+
+        fn try_from(json: MerkleProofJson) -> Result<Self, Self::Error> {
+            Ok(Self {
+                leaf: decode(&json.leaf)?,
+                index: json.index,
+                max_depth: json.max_depth,
+                siblings: json
+                    .siblings
+                    .iter()
+                    .map(|sibling| decode(sibling))
+                    .collect::<Result<_, _>>()?,
+                root: decode(&json.root)?,
+                hash_kind: json.hash_kind,
+                domain_separated: json.domain_separated,
+            })
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::errors::MerkleTreeStorageError;
+use crate::{multiproof_from_nodes, proof_from_nodes, root_from_nodes, Hasher, EMPTY_NODE};
+
+/// Off-chain mirror of a `MerkleTree` account of any depth. Leaves are hashed and
+/// stored the way the program does it, so `nodes` stays equal to the account's:
+/// heap-ordered (root first, then each level left to right), with the nodes of
+/// empty subtrees left zeroed and removed leaves stored as `[0; 32]`.
+///
+/// `hasher` must match the tree's scheme, for example `HashKind::Sha256` or
+/// `DomainSeparated(HashKind::Sha256)` for a domain-separated tree, and
+/// `allow_leaf_reuse` the tree's CreateTree argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OffchainMerkleTree<H: Hasher> {
+    pub hasher: H,
+    pub max_depth: u8,
+    /// Whether `replace_leaf` may fill a removed leaf.
+    pub allow_leaf_reuse: bool,
+    pub next_leaf_index: u32,
+    pub nodes: Vec<[u8; 32]>,
+}
+
+impl<H: Hasher> OffchainMerkleTree<H> {
+    /// An empty tree with `2^max_depth` leaf slots.
+    pub fn new(hasher: H, max_depth: u8, allow_leaf_reuse: bool) -> Self {
+        Self {
+            hasher,
+            max_depth,
+            allow_leaf_reuse,
+            next_leaf_index: 0,
+            nodes: vec![EMPTY_NODE; (1 << (max_depth as usize + 1)) - 1],
+        }
+    }
+
+    /// A tree holding the `nodes` of an account, `next_leaf_index` leaves inserted,
+    /// or `None` if `max_depth` is above 32, there are not `2^(max_depth + 1) - 1`
+    /// nodes, or there are more leaves than slots.
+    pub fn from_nodes(
+        hasher: H,
+        max_depth: u8,
+        allow_leaf_reuse: bool,
+        next_leaf_index: u32,
+        nodes: Vec<[u8; 32]>,
+    ) -> Option<Self> {
+        if max_depth > 32 {
+            return None;
+        }
+        let tree = Self {
+            hasher,
+            max_depth,
+            allow_leaf_reuse,
+            next_leaf_index,
+            nodes,
+        };
+        if tree.nodes.len() != 2 * tree.capacity() - 1 || next_leaf_index as usize > tree.capacity()
+        {
+            return None;
+        }
+        Some(tree)
+    }
+
+    pub fn capacity(&self) -> usize {
+        1 << self.max_depth
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        root_from_nodes(&self.hasher, &self.nodes, self.max_depth)
+    }
+
+    /// Node stored for the leaf at `index`, as in the account, or `None` past
+    /// the last slot.
+    pub fn leaf(&self, index: u32) -> Option<[u8; 32]> {
+        self.nodes
+            .get(self.capacity() - 1 + index as usize)
+            .copied()
+    }
+
+    /// Sibling path of the leaf at `index`, as taken by VerifyLeaf, or `None` past
+    /// the last slot.
+    pub fn proof(&self, index: u32) -> Option<Vec<[u8; 32]>> {
+        self.has_slot(index)
+            .then(|| proof_from_nodes(&self.hasher, &self.nodes, self.max_depth, index))
+    }
+
+    /// Multiproof of the leaves at `indices`, strictly increasing, as taken by
+    /// VerifyLeaves, or `None` if an index is past the last slot.
+    pub fn multiproof(&self, indices: &[u32]) -> Option<Vec<[u8; 32]>> {
+        indices
+            .iter()
+            .all(|index| self.has_slot(*index))
+            .then(|| multiproof_from_nodes(&self.hasher, &self.nodes, self.max_depth, indices))
+    }
+
+    /// Appends `leaf` like InsertLeaf, returning its index.
+    pub fn insert_leaf(&mut self, leaf: [u8; 32]) -> Result<u32, MerkleTreeStorageError> {
+        self.insert_leaves(&[leaf])
+    }
+
+    /// Appends `leaves` like InsertLeaves, all or nothing, returning the first index.
+    pub fn insert_leaves(&mut self, leaves: &[[u8; 32]]) -> Result<u32, MerkleTreeStorageError> {
+        if self.next_leaf_index as usize + leaves.len() > self.capacity() {
+            return Err(MerkleTreeStorageError::TreeOverflow);
+        }
+        if leaves.contains(&EMPTY_NODE) {
+            return Err(MerkleTreeStorageError::InvalidLeaf);
+        }
+        let start_index = self.next_leaf_index;
+        for leaf in leaves {
+            self.set_leaf(self.next_leaf_index, self.hasher.hash_leaf(leaf));
+            self.next_leaf_index += 1;
+        }
+        Ok(start_index)
+    }
+
+    /// Overwrites the leaf at `index`, currently `previous_leaf`, like ReplaceLeaf.
+    pub fn replace_leaf(
+        &mut self,
+        index: u32,
+        previous_leaf: [u8; 32],
+        new_leaf: [u8; 32],
+    ) -> Result<(), MerkleTreeStorageError> {
+        self.check_leaf(index, previous_leaf)?;
+        if new_leaf == EMPTY_NODE {
+            return Err(MerkleTreeStorageError::InvalidLeaf);
+        }
+        if previous_leaf == EMPTY_NODE && !self.allow_leaf_reuse {
+            return Err(MerkleTreeStorageError::LeafRemoved);
+        }
+        self.set_leaf(index, self.leaf_node(new_leaf));
+        Ok(())
+    }
+
+    /// Empties the leaf at `index`, currently `previous_leaf`, like RemoveLeaf.
+    pub fn remove_leaf(
+        &mut self,
+        index: u32,
+        previous_leaf: [u8; 32],
+    ) -> Result<(), MerkleTreeStorageError> {
+        self.check_leaf(index, previous_leaf)?;
+        if previous_leaf == EMPTY_NODE {
+            return Err(MerkleTreeStorageError::LeafRemoved);
+        }
+        self.set_leaf(index, EMPTY_NODE);
+        Ok(())
+    }
+
+    fn has_slot(&self, index: u32) -> bool {
+        (index as usize) < self.capacity()
+    }
+
+    // Same checks and errors as the program, so a change that this copy accepts
+    // will not fail on-chain for a stale `expected` value.
+    fn check_leaf(&self, index: u32, expected: [u8; 32]) -> Result<(), MerkleTreeStorageError> {
+        if index >= self.next_leaf_index {
+            return Err(MerkleTreeStorageError::LeafIndexOutOfBounds);
+        }
+        if self.leaf(index) != Some(self.leaf_node(expected)) {
+            return Err(MerkleTreeStorageError::LeafMismatch);
+        }
+        Ok(())
+    }
+
+    // `nodes` must stay byte-for-byte equal to the account's, where `[0; 32]`
+    // marks a removed leaf even in domain-separated trees
+    fn leaf_node(&self, leaf: [u8; 32]) -> [u8; 32] {
+        if leaf == EMPTY_NODE {
+            return leaf;
+        }
+        self.hasher.hash_leaf(&leaf)
+    }
+
+    // callers have checked `index`; updates every ancestor of the leaf
+    fn set_leaf(&mut self, index: u32, node: [u8; 32]) {
+        let zero_hashes = self.hasher.zero_hashes(self.max_depth);
+        let node_at = |node: [u8; 32], height: usize| match node {
+            EMPTY_NODE => zero_hashes[height],
+            node => node,
+        };
+        let mut current = self.capacity() - 1 + index as usize;
+        self.nodes[current] = node;
+        let mut height = 0;
+        while current > 0 {
+            let parent = (current - 1) / 2;
+            let left = node_at(self.nodes[2 * parent + 1], height);
+            let right = node_at(self.nodes[2 * parent + 2], height);
+            self.nodes[parent] = self.hasher.hash_nodes(&left, &right);
+            current = parent;
+            height += 1;
+        }
+    }
+}
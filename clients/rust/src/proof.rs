@@ -0,0 +1,107 @@
+use crate::accounts::ConcurrentMerkleTree;
+use crate::{multiproof_positions, Hasher, EMPTY_NODE};
+
+/// Sibling path of the leaf at `index`, from the leaf level up, read from the
+/// heap-ordered `nodes` of a tree account (root first, then each level left to right).
+/// Zeroed nodes stand for empty subtrees and are replaced by `hasher`'s zero hashes.
+pub fn proof_from_nodes(
+    hasher: &impl Hasher,
+    nodes: &[[u8; 32]],
+    max_depth: u8,
+    index: u32,
+) -> Vec<[u8; 32]> {
+    let zero_hashes = hasher.zero_hashes(max_depth);
+    let mut proof = Vec::with_capacity(max_depth as usize);
+    let mut current = (1 << max_depth) - 1 + index as usize;
+    while current > 0 {
+        let sibling = if current % 2 == 1 {
+            current + 1
+        } else {
+            current - 1
+        };
+        proof.push(match nodes[sibling] {
+            EMPTY_NODE => zero_hashes[proof.len()],
+            node => node,
+        });
+        current = (current - 1) / 2;
+    }
+    proof
+}
+
+/// Root of a tree account's `nodes`; a zeroed root means the tree holds no leaf yet.
+pub fn root_from_nodes(hasher: &impl Hasher, nodes: &[[u8; 32]], max_depth: u8) -> [u8; 32] {
+    match nodes[0] {
+        EMPTY_NODE => hasher.zero_hashes(max_depth)[max_depth as usize],
+        root => root,
+    }
+}
+
+/// Checks that `proof` links `leaf` at `index` to `root` in a tree of `max_depth`
+/// levels, hashing with the tree's scheme (for example
+/// `DomainSeparated(HashKind::Sha256)` for a domain-separated SHA-256 tree). Like
+/// VerifyLeaf, it wants one sibling per level, so that an internal node cannot pass
+/// for a leaf with a shorter proof.
+pub fn verify_proof(
+    hasher: &impl Hasher,
+    root: &[u8; 32],
+    leaf: &[u8; 32],
+    index: u32,
+    proof: &[[u8; 32]],
+    max_depth: u8,
+) -> bool {
+    if max_depth > 32 || proof.len() != max_depth as usize {
+        return false;
+    }
+    // the index must fit in the tree
+    if max_depth < 32 && index >> max_depth != 0 {
+        return false;
+    }
+    hasher.compute_root(leaf, index, proof) == *root
+}
+
+/// Multiproof of the leaves at `indices`, strictly increasing, read from a tree
+/// account's `nodes`: each sibling their paths need is included once, in the order
+/// VerifyLeaves reads them.
+pub fn multiproof_from_nodes(
+    hasher: &impl Hasher,
+    nodes: &[[u8; 32]],
+    max_depth: u8,
+    indices: &[u32],
+) -> Vec<[u8; 32]> {
+    let zero_hashes = hasher.zero_hashes(max_depth);
+    multiproof_positions(indices, max_depth)
+        .into_iter()
+        .map(|(height, index)| {
+            let level_start = (1 << (max_depth - height)) - 1;
+            match nodes[level_start + index as usize] {
+                EMPTY_NODE => zero_hashes[height as usize],
+                node => node,
+            }
+        })
+        .collect()
+}
+
+/// Checks that the multiproof `proof` links each `(index, leaf)` of `leaves`, by
+/// strictly increasing index, to `root`, hashing with the tree's scheme.
+pub fn verify_multiproof(
+    hasher: &impl Hasher,
+    root: &[u8; 32],
+    leaves: &[(u32, [u8; 32])],
+    proof: &[[u8; 32]],
+    max_depth: u8,
+) -> bool {
+    hasher.compute_multiproof_root(leaves, proof, max_depth) == Some(*root)
+}
+
+/// `proof` without the top siblings held by a canopy of `canopy_depth` levels,
+/// which is all ReplaceConcurrentLeaf and VerifyConcurrentLeaf need.
+pub fn trim_proof(proof: &[[u8; 32]], canopy_depth: u8) -> Vec<[u8; 32]> {
+    proof[..proof.len().saturating_sub(canopy_depth as usize)].to_vec()
+}
+
+impl ConcurrentMerkleTree {
+    /// `proof` trimmed to this tree's canopy, see [`trim_proof`].
+    pub fn trim_proof(&self, proof: &[[u8; 32]]) -> Vec<[u8; 32]> {
+        trim_proof(proof, self.canopy_depth)
+    }
+}
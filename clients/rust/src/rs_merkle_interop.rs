@@ -0,0 +1,65 @@
+use std::marker::PhantomData;
+
+use crate::{DomainSeparated, Hasher, Keccak256Hasher, MerkleProof, Sha256Hasher, TreeHasher};
+
+/// `rs_merkle` hasher matching the program's `H`: data hashes with `hashv` and two
+/// nodes with `hash_nodes`. rs_merkle takes leaves already hashed, so pass it the
+/// nodes the program stores, `H::hash_leaf(leaf)`.
+///
+/// Roots agree for trees filled to capacity. In a partially filled tree rs_merkle
+/// promotes a node without a right sibling unchanged and stops at the height its
+/// leaf count needs, while the program pairs it with the root of the empty subtree
+/// up to `max_depth`; padding the rs_merkle leaves with `[0; 32]` up to
+/// `2^max_depth` makes the roots agree again.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RsMerkleHasher<H>(PhantomData<H>);
+
+pub type RsSha256 = RsMerkleHasher<Sha256Hasher>;
+pub type RsKeccak256 = RsMerkleHasher<Keccak256Hasher>;
+pub type RsDomainSeparatedSha256 = RsMerkleHasher<DomainSeparated<Sha256Hasher>>;
+pub type RsDomainSeparatedKeccak256 = RsMerkleHasher<DomainSeparated<Keccak256Hasher>>;
+
+impl<H: Hasher + Clone + Default> rs_merkle::Hasher for RsMerkleHasher<H> {
+    type Hash = [u8; 32];
+
+    fn hash(data: &[u8]) -> [u8; 32] {
+        H::default().hashv(&[data])
+    }
+
+    fn concat_and_hash(left: &[u8; 32], right: Option<&[u8; 32]>) -> [u8; 32] {
+        match right {
+            Some(right) => H::default().hash_nodes(left, right),
+            None => *left,
+        }
+    }
+}
+
+impl MerkleProof {
+    /// rs_merkle proof holding the siblings, to check with
+    /// `verify(root, &[index], &[hasher().hash_leaf(&leaf)], 1 << max_depth)`.
+    pub fn to_rs_merkle<T: rs_merkle::Hasher<Hash = [u8; 32]>>(&self) -> rs_merkle::MerkleProof<T> {
+        rs_merkle::MerkleProof::new(self.siblings.clone())
+    }
+
+    /// Proof of `leaf` at `index` from an rs_merkle proof of its hashed leaf in a
+    /// tree of `max_depth` levels. Only proofs from a tree of `2^max_depth` leaves
+    /// carry a sibling per level, see [`RsMerkleHasher`].
+    pub fn from_rs_merkle<T: rs_merkle::Hasher<Hash = [u8; 32]>>(
+        proof: &rs_merkle::MerkleProof<T>,
+        leaf: [u8; 32],
+        index: u32,
+        max_depth: u8,
+        root: [u8; 32],
+        hasher: TreeHasher,
+    ) -> Self {
+        Self {
+            leaf,
+            index,
+            max_depth,
+            siblings: proof.proof_hashes().to_vec(),
+            root,
+            hash_kind: hasher.hash_kind,
+            domain_separated: hasher.domain_separated,
+        }
+    }
+}
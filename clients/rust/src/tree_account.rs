@@ -0,0 +1,76 @@
+use crate::accounts::MerkleTree;
+use crate::{
+    multiproof_from_nodes, proof_from_nodes, root_from_nodes, MerkleProof, OffchainMerkleTree,
+    TreeHasher,
+};
+
+/// Reads of a fetched `MerkleTree` account, so that a single fetch gives the
+/// root, the leaves and their proofs.
+impl MerkleTree {
+    pub fn hasher(&self) -> TreeHasher {
+        TreeHasher {
+            hash_kind: self.hash_kind,
+            domain_separated: self.domain_separated,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        1 << self.max_depth
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.next_leaf_index as usize >= self.capacity()
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        root_from_nodes(&self.hasher(), &self.nodes, self.max_depth)
+    }
+
+    /// Node stored for the leaf at `index`: the leaf itself, or its hash in
+    /// domain-separated trees, and `[0; 32]` once removed.
+    pub fn leaf(&self, index: u32) -> [u8; 32] {
+        self.nodes[self.capacity() - 1 + index as usize]
+    }
+
+    /// Nodes stored for the inserted leaves, by index.
+    pub fn leaves(&self) -> &[[u8; 32]] {
+        let first = self.capacity() - 1;
+        &self.nodes[first..first + self.next_leaf_index as usize]
+    }
+
+    /// Sibling path of the leaf at `index`, as taken by VerifyLeaf.
+    pub fn proof(&self, index: u32) -> Vec<[u8; 32]> {
+        proof_from_nodes(&self.hasher(), &self.nodes, self.max_depth, index)
+    }
+
+    /// Multiproof of the leaves at `indices`, strictly increasing, as taken by VerifyLeaves.
+    pub fn multiproof(&self, indices: &[u32]) -> Vec<[u8; 32]> {
+        multiproof_from_nodes(&self.hasher(), &self.nodes, self.max_depth, indices)
+    }
+
+    /// Portable proof of `leaf`, the value inserted at `index`, against the
+    /// current root.
+    pub fn merkle_proof(&self, index: u32, leaf: [u8; 32]) -> MerkleProof {
+        MerkleProof {
+            leaf,
+            index,
+            max_depth: self.max_depth,
+            siblings: self.proof(index),
+            root: self.root(),
+            hash_kind: self.hash_kind,
+            domain_separated: self.domain_separated,
+        }
+    }
+
+    /// Off-chain copy of the tree, to follow its next changes, or `None` if the
+    /// account's nodes do not match its depth.
+    pub fn to_offchain_tree(&self) -> Option<OffchainMerkleTree<TreeHasher>> {
+        OffchainMerkleTree::from_nodes(
+            self.hasher(),
+            self.max_depth,
+            self.allow_leaf_reuse,
+            self.next_leaf_index,
+            self.nodes.clone(),
+        )
+    }
+}
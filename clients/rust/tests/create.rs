@@ -1,9 +1,21 @@
 #![cfg(feature = "test-sbf")]
 
+use assert_matches::assert_matches;
 use borsh::BorshDeserialize;
 use merkle_tree_storage::{
-    accounts::MerkleTree,
-    instructions::{CreateTreeBuilder, InsertLeafBuilder},
+    accounts::{ConcurrentMerkleTree, IncrementalMerkleTree, MerkleTree},
+    types::{HashKind, LeafInserted, LeafReplaced, MerkleTreeEvent, TreeCreated, TreeKind},
+    instructions::{
+        AcceptAuthorityBuilder, AddDelegateBuilder, AppendConcurrentLeafBuilder, AppendLeafBuilder,
+        CloseTreeBuilder, CreateConcurrentTreeBuilder, CreateIncrementalTreeBuilder,
+        CreateTreeBuilder, EmitEventBuilder, GrowTreeBuilder, InsertLeafBuilder,
+        InsertLeavesBuilder, RemoveDelegateBuilder, RemoveLeafBuilder, ReplaceConcurrentLeafBuilder,
+        ReplaceLeafBuilder, SetAuthorityBuilder, VerifyConcurrentLeafBuilder, VerifyLeafBuilder,
+        VerifyLeavesBuilder,
+    },
+    event_cpi_accounts, events_from_logs, events_from_transaction, find_event_authority,
+    proof_from_nodes, root_from_nodes, trim_proof, verify_proof, DomainSeparated, Hasher,
+    OffchainMerkleTree, Sha256Hasher, TreeHasher,
 };
 use sha2::Sha256;
 use sha3::{Digest, Keccak256};
@@ -13,15 +25,20 @@ use solana_program_test::{
 };
 use solana_sdk::{
     instruction::{Instruction, InstructionError},
+    message::{Message, VersionedMessage},
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
+    signature::{Keypair, Signature, Signer},
     system_instruction::transfer,
     system_program, sysvar,
-    transaction::{Transaction, TransactionError},
+    transaction::{Transaction, TransactionError, VersionedTransaction},
+};
+use solana_transaction_status::{
+    InnerInstruction, InnerInstructions, TransactionStatusMeta, UiTransactionEncoding,
+    VersionedTransactionWithStatusMeta,
 };
 
-mod off_chain_tree;
-use off_chain_tree::OffchainMerkleTree;
+// value the program stores for removed leaves
+const EMPTY_LEAF: [u8; 32] = [0; 32];
 
 fn keccak256(data: &[u8]) -> [u8; 32] {
     let mut hasher = Keccak256::new();
@@ -40,15 +57,17 @@ async fn get_context() -> SharedContext {
             .start_with_context()
             .await;
 
-    let (tree_pda, _bump) = Pubkey::find_program_address(
-        &[b"tree", context.payer.pubkey().as_ref()],
-        &merkle_tree_storage::ID,
-    );
+    let (tree_pda, _bump) = MerkleTree::find_pda(&context.payer.pubkey(), 0);
 
     let ix_create_tree = CreateTreeBuilder::new()
         .payer(context.payer.pubkey())
         .tree(tree_pda)
         .max_depth(3)
+        .tree_id(0)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .root_history_size(0)
         .system_program(system_program::ID)
         .sysvar_rent(sysvar::rent::ID)
         .instruction();
@@ -100,7 +119,7 @@ async fn prevent_insert_leaf_to_wrong_account() {
         .unwrap();
 
     let ix_insert_leaf = InsertLeafBuilder::new()
-        .payer(hacker.pubkey())
+        .authority(hacker.pubkey())
         .tree(shared.tree_pda)
         .leaf(keccak256(&[1; 32]))
         .instruction();
@@ -112,7 +131,7 @@ async fn prevent_insert_leaf_to_wrong_account() {
         shared.context.last_blockhash,
     );
 
-    //process transaction and expect custom error with code 4
+    //process transaction and expect custom error with code 12
     let error = shared
         .context
         .banks_client
@@ -128,9 +147,9 @@ async fn prevent_insert_leaf_to_wrong_account() {
             "Instruction {} failed with custom error code: 0x{:X}",
             index, code
         );
-        assert_eq!(code, 4);
+        assert_eq!(code, 12);
     } else {
-        panic!("Expected custom error with code 4");
+        panic!("Expected custom error with code 12");
     }
 }
 #[tokio::test]
@@ -138,7 +157,7 @@ async fn insert_leaf() {
     let mut shared = get_context().await;
 
     let ix_insert_first_leaf = InsertLeafBuilder::new()
-        .payer(shared.context.payer.pubkey())
+        .authority(shared.context.payer.pubkey())
         .tree(shared.tree_pda)
         .leaf([1; 32])
         .instruction();
@@ -169,11 +188,11 @@ async fn insert_leaf() {
     let mut account_data = account.data.as_ref();
     let my_account = MerkleTree::deserialize(&mut account_data).unwrap();
     assert_eq!(my_account.next_leaf_index, 1);
+    // The header and node region cover the whole account, with no trailing bytes.
+    assert!(account_data.is_empty());
+    assert_eq!(my_account.nodes.len(), 15);
 
-    let mut tree = OffchainMerkleTree {
-        nodes: vec![[0; 32]; OffchainMerkleTree::TREE_SIZE],
-        next_leaf_index: 0,
-    };
+    let mut tree = OffchainMerkleTree::new(Sha256Hasher, 3, false);
 
     tree.insert_leaf([1; 32]).unwrap();
 
@@ -181,6 +200,322 @@ async fn insert_leaf() {
     assert_eq!(root, my_account.nodes[0]);
 }
 
+#[tokio::test]
+async fn create_multiple_trees_per_payer() {
+    let mut shared = get_context().await;
+
+    let (second_tree_pda, _bump) = MerkleTree::find_pda(&shared.context.payer.pubkey(), 1);
+    assert_ne!(second_tree_pda, shared.tree_pda);
+
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(shared.context.payer.pubkey())
+        .tree(second_tree_pda)
+        .max_depth(3)
+        .tree_id(1)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .root_history_size(0)
+        .instruction();
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(second_tree_pda)
+        .leaf([2; 32])
+        .instruction();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree, ix_insert_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let first_account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let first_tree = MerkleTree::from_bytes(&first_account.data).unwrap();
+    assert_eq!(first_tree.next_leaf_index, 0);
+
+    let second_account = shared
+        .context
+        .banks_client
+        .get_account(second_tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let second_tree = MerkleTree::from_bytes(&second_account.data).unwrap();
+    assert_eq!(second_tree.next_leaf_index, 1);
+}
+
+#[tokio::test]
+async fn reject_invalid_max_depth() {
+    let mut shared = get_context().await;
+
+    for (tree_id, max_depth) in [(1, 0), (2, 18)] {
+        let (tree_pda, _bump) = MerkleTree::find_pda(&shared.context.payer.pubkey(), tree_id);
+        let ix_create_tree = CreateTreeBuilder::new()
+            .payer(shared.context.payer.pubkey())
+            .tree(tree_pda)
+            .max_depth(max_depth)
+            .tree_id(tree_id)
+            .allow_leaf_reuse(false)
+            .hash_kind(HashKind::Sha256)
+            .domain_separated(false)
+            .root_history_size(0)
+            .instruction();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix_create_tree],
+            Some(&shared.context.payer.pubkey()),
+            &[&shared.context.payer],
+            shared.context.last_blockhash,
+        );
+        let error = shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(6)
+            ))
+        );
+    }
+}
+
+#[tokio::test]
+async fn insert_more_than_256_leaves() {
+    let mut shared = get_context().await;
+    let max_depth = 9;
+    let leaves_count: u32 = 300;
+
+    // A depth 9 tree takes about 32 KiB, more than one CreateTree can allocate,
+    // so the instruction is repeated until the account reaches its full size.
+    let (tree_pda, _bump) = MerkleTree::find_pda(&shared.context.payer.pubkey(), 1);
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .max_depth(max_depth)
+        .tree_id(1)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .root_history_size(0)
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree.clone(), ix_create_tree.clone()],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    // The tree is not usable until it is fully allocated.
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .leaf([1; 32])
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_insert_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(1)
+        ))
+    );
+
+    let blockhash = shared.context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree.clone(), ix_create_tree],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let leaves: Vec<[u8; 32]> = (0..leaves_count)
+        .map(|i| keccak256(&i.to_le_bytes()))
+        .collect();
+    for chunk in leaves.chunks(20) {
+        let ixs: Vec<Instruction> = chunk
+            .iter()
+            .map(|leaf| {
+                InsertLeafBuilder::new()
+                    .authority(shared.context.payer.pubkey())
+                    .tree(tree_pda)
+                    .leaf(*leaf)
+                    .instruction()
+            })
+            .collect();
+        let tx = Transaction::new_signed_with_payer(
+            &ixs,
+            Some(&shared.context.payer.pubkey()),
+            &[&shared.context.payer],
+            shared.context.last_blockhash,
+        );
+        shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap();
+    }
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.next_leaf_index, leaves_count);
+    let last_leaf_pos = (1 << max_depth) - 1 + leaves_count as usize - 1;
+    assert_eq!(my_account.nodes[last_leaf_pos], leaves[leaves.len() - 1]);
+}
+
+#[tokio::test]
+async fn only_the_payer_finishes_creating_a_tree() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+    let attacker = Keypair::new();
+
+    let (tree_pda, _bump) = MerkleTree::find_pda(&payer, 1);
+    let create = |authority: Option<Pubkey>| {
+        let mut builder = CreateTreeBuilder::new();
+        builder
+            .payer(payer)
+            .tree(tree_pda)
+            .max_depth(10)
+            .tree_id(1)
+            .allow_leaf_reuse(false)
+            .hash_kind(HashKind::Sha256)
+            .domain_separated(false)
+            .root_history_size(0);
+        if let Some(authority) = authority {
+            builder.authority(authority);
+        }
+        builder.instruction()
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            create(None),
+            transfer(&payer, &attacker.pubkey(), 1_000_000_000),
+        ],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    // follow-up calls need the payer's signature
+    let mut ix_unsigned = create(Some(attacker.pubkey()));
+    ix_unsigned.accounts[0].is_signer = false;
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_unsigned],
+        Some(&attacker.pubkey()),
+        &[&attacker],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(5)
+        ))
+    );
+
+    // and must repeat the args of the first call
+    let tx = Transaction::new_signed_with_payer(
+        &[create(Some(attacker.pubkey()))],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &vec![create(None); 6],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.authority, payer);
+    assert_eq!(my_account.capacity(), 1024);
+    assert_eq!(my_account.root(), Sha256Hasher.zero_hashes(10)[10]);
+}
+
 #[tokio::test]
 async fn insert_maximum_leafs() {
     let mut shared = get_context().await;
@@ -198,7 +533,7 @@ async fn insert_maximum_leafs() {
 
     let build_insert_leaf_ix = |leaf: [u8; 32]| {
         InsertLeafBuilder::new()
-            .payer(shared.context.payer.pubkey())
+            .authority(shared.context.payer.pubkey())
             .tree(shared.tree_pda)
             .leaf(leaf)
             .instruction()
@@ -230,12 +565,9 @@ async fn insert_maximum_leafs() {
 
     let mut account_data = account.data.as_ref();
     let my_account = MerkleTree::deserialize(&mut account_data).unwrap();
-    assert_eq!(my_account.next_leaf_index, leaves.len() as u8);
+    assert_eq!(my_account.next_leaf_index, leaves.len() as u32);
 
-    let mut tree = OffchainMerkleTree {
-        nodes: vec![[0; 32]; OffchainMerkleTree::TREE_SIZE],
-        next_leaf_index: 0,
-    };
+    let mut tree = OffchainMerkleTree::new(Sha256Hasher, 3, false);
 
     for leaf in leaves {
         tree.insert_leaf(leaf).unwrap();
@@ -264,7 +596,7 @@ async fn overflow_tree() {
 
     let build_insert_leaf_ix = |leaf: [u8; 32]| {
         InsertLeafBuilder::new()
-            .payer(shared.context.payer.pubkey())
+            .authority(shared.context.payer.pubkey())
             .tree(shared.tree_pda)
             .leaf(leaf)
             .instruction()
@@ -298,3 +630,3000 @@ async fn overflow_tree() {
         panic!("Expected custom error with code 4");
     }
 }
+
+#[tokio::test]
+async fn verify_leaf() {
+    let mut shared = get_context().await;
+
+    let leaves = [keccak256(b"First"), keccak256(b"Second"), keccak256(b"Third")];
+    let ixs: Vec<Instruction> = leaves
+        .iter()
+        .map(|leaf| {
+            InsertLeafBuilder::new()
+                .authority(shared.context.payer.pubkey())
+                .tree(shared.tree_pda)
+                .leaf(*leaf)
+                .instruction()
+        })
+        .collect();
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    let proof = proof_from_nodes(&my_account.hash_kind, &my_account.nodes, my_account.max_depth, 1);
+
+    let ix_verify_leaf = VerifyLeafBuilder::new()
+        .tree(shared.tree_pda)
+        .leaf(leaves[1])
+        .index(1)
+        .proof(proof.clone())
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_verify_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    // wrong leaf, wrong index, truncated proof and a not yet inserted index all fail
+    let invalid_cases = [
+        (leaves[0], 1, proof.clone()),
+        (leaves[1], 0, proof.clone()),
+        (leaves[1], 1, proof[..2].to_vec()),
+        ([0; 32], 3, proof_from_nodes(&my_account.hash_kind, &my_account.nodes, my_account.max_depth, 3)),
+    ];
+    for (leaf, index, proof) in invalid_cases {
+        let ix_verify_leaf = VerifyLeafBuilder::new()
+            .tree(shared.tree_pda)
+            .leaf(leaf)
+            .index(index)
+            .proof(proof)
+            .instruction();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix_verify_leaf],
+            Some(&shared.context.payer.pubkey()),
+            &[&shared.context.payer],
+            shared.context.last_blockhash,
+        );
+        let error = shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(7)
+            ))
+        );
+    }
+}
+
+#[tokio::test]
+async fn replace_leaf() {
+    let mut shared = get_context().await;
+
+    let leaves = [keccak256(b"First"), keccak256(b"Second")];
+    let ixs: Vec<Instruction> = leaves
+        .iter()
+        .map(|leaf| {
+            InsertLeafBuilder::new()
+                .authority(shared.context.payer.pubkey())
+                .tree(shared.tree_pda)
+                .leaf(*leaf)
+                .instruction()
+        })
+        .collect();
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let new_leaf = keccak256(b"Amended");
+    let ix_replace_leaf = ReplaceLeafBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .index(1)
+        .previous_leaf(leaves[1])
+        .new_leaf(new_leaf)
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_replace_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.next_leaf_index, 2);
+
+    let mut tree = OffchainMerkleTree::new(Sha256Hasher, 3, false);
+    tree.insert_leaf(leaves[0]).unwrap();
+    tree.insert_leaf(new_leaf).unwrap();
+    assert_eq!(tree.nodes[0], my_account.nodes[0]);
+
+    // an index that was never inserted, then a stale previous leaf
+    let invalid_cases = [(2, [0; 32], 8), (1, leaves[1], 9)];
+    for (index, previous_leaf, code) in invalid_cases {
+        let ix_replace_leaf = ReplaceLeafBuilder::new()
+            .authority(shared.context.payer.pubkey())
+            .tree(shared.tree_pda)
+            .index(index)
+            .previous_leaf(previous_leaf)
+            .new_leaf(keccak256(b"Other"))
+            .instruction();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix_replace_leaf],
+            Some(&shared.context.payer.pubkey()),
+            &[&shared.context.payer],
+            shared.context.last_blockhash,
+        );
+        let error = shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(error_code)
+            )) if error_code == code
+        );
+    }
+}
+
+#[tokio::test]
+async fn remove_leaf() {
+    let mut shared = get_context().await;
+
+    let leaves = [keccak256(b"First"), keccak256(b"Second")];
+    let mut ixs: Vec<Instruction> = leaves
+        .iter()
+        .map(|leaf| {
+            InsertLeafBuilder::new()
+                .authority(shared.context.payer.pubkey())
+                .tree(shared.tree_pda)
+                .leaf(*leaf)
+                .instruction()
+        })
+        .collect();
+    ixs.push(
+        RemoveLeafBuilder::new()
+            .authority(shared.context.payer.pubkey())
+            .tree(shared.tree_pda)
+            .index(0)
+            .previous_leaf(leaves[0])
+            .instruction(),
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.next_leaf_index, 2);
+
+    let mut tree = OffchainMerkleTree::new(Sha256Hasher, 3, false);
+    tree.insert_leaves(&leaves).unwrap();
+    tree.remove_leaf(0, leaves[0]).unwrap();
+    assert_eq!(tree.nodes, my_account.nodes);
+
+    // removing twice, refilling a removed leaf without reuse, inserting the empty value
+    let invalid_ixs = [
+        (
+            RemoveLeafBuilder::new()
+                .authority(shared.context.payer.pubkey())
+                .tree(shared.tree_pda)
+                .index(0)
+                .previous_leaf(EMPTY_LEAF)
+                .instruction(),
+            11,
+        ),
+        (
+            ReplaceLeafBuilder::new()
+                .authority(shared.context.payer.pubkey())
+                .tree(shared.tree_pda)
+                .index(0)
+                .previous_leaf(EMPTY_LEAF)
+                .new_leaf(leaves[0])
+                .instruction(),
+            11,
+        ),
+        (
+            InsertLeafBuilder::new()
+                .authority(shared.context.payer.pubkey())
+                .tree(shared.tree_pda)
+                .leaf(EMPTY_LEAF)
+                .instruction(),
+            10,
+        ),
+        (
+            VerifyLeafBuilder::new()
+                .tree(shared.tree_pda)
+                .leaf(EMPTY_LEAF)
+                .index(0)
+                .proof(proof_from_nodes(&my_account.hash_kind, &my_account.nodes, 3, 0))
+                .instruction(),
+            7,
+        ),
+    ];
+    for (ix, code) in invalid_ixs {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&shared.context.payer.pubkey()),
+            &[&shared.context.payer],
+            shared.context.last_blockhash,
+        );
+        let error = shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(error_code)
+            )) if error_code == code
+        );
+    }
+
+    // a tree created with leaf reuse lets a removed leaf be filled again
+    let (reuse_tree_pda, _bump) = MerkleTree::find_pda(&shared.context.payer.pubkey(), 1);
+    let ixs = [
+        CreateTreeBuilder::new()
+            .payer(shared.context.payer.pubkey())
+            .tree(reuse_tree_pda)
+            .max_depth(3)
+            .tree_id(1)
+            .allow_leaf_reuse(true)
+            .hash_kind(HashKind::Sha256)
+            .domain_separated(false)
+            .root_history_size(0)
+            .instruction(),
+        InsertLeafBuilder::new()
+            .authority(shared.context.payer.pubkey())
+            .tree(reuse_tree_pda)
+            .leaf(leaves[0])
+            .instruction(),
+        RemoveLeafBuilder::new()
+            .authority(shared.context.payer.pubkey())
+            .tree(reuse_tree_pda)
+            .index(0)
+            .previous_leaf(leaves[0])
+            .instruction(),
+        ReplaceLeafBuilder::new()
+            .authority(shared.context.payer.pubkey())
+            .tree(reuse_tree_pda)
+            .index(0)
+            .previous_leaf(EMPTY_LEAF)
+            .new_leaf(leaves[1])
+            .instruction(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(reuse_tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let reuse_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert!(reuse_account.allow_leaf_reuse);
+    assert_eq!(reuse_account.nodes[7], leaves[1]);
+}
+
+#[tokio::test]
+async fn insert_leaves() {
+    let mut shared = get_context().await;
+
+    let leaves: Vec<[u8; 32]> = (0..8u8).map(|i| keccak256(&[i])).collect();
+    // one single insert first so the batch starts at an odd index
+    let ixs = [
+        InsertLeafBuilder::new()
+            .authority(shared.context.payer.pubkey())
+            .tree(shared.tree_pda)
+            .leaf(leaves[0])
+            .instruction(),
+        InsertLeavesBuilder::new()
+            .authority(shared.context.payer.pubkey())
+            .tree(shared.tree_pda)
+            .leaves(leaves[1..6].to_vec())
+            .instruction(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.next_leaf_index, 6);
+
+    let mut tree = OffchainMerkleTree::new(Sha256Hasher, 3, false);
+    for leaf in &leaves[..6] {
+        tree.insert_leaf(*leaf).unwrap();
+    }
+    assert_eq!(tree.nodes, my_account.nodes);
+
+    // three leaves do not fit in the two free slots, so none is inserted
+    let ix_insert_leaves = InsertLeavesBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .leaves(vec![leaves[6], leaves[7], leaves[0]])
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_insert_leaves],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(3)
+        ))
+    );
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.next_leaf_index, 6);
+    assert_eq!(tree.nodes, my_account.nodes);
+}
+
+#[tokio::test]
+async fn separate_authority() {
+    let mut shared = get_context().await;
+    let authority = Keypair::new();
+    let new_authority = Keypair::new();
+
+    let (tree_pda, _bump) = MerkleTree::find_pda(&shared.context.payer.pubkey(), 1);
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .max_depth(3)
+        .tree_id(1)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .root_history_size(0)
+        .authority(authority.pubkey())
+        .instruction();
+    // the payer only pays the fees, the authority signs the insert
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(authority.pubkey())
+        .tree(tree_pda)
+        .leaf([1; 32])
+        .instruction();
+    let ix_set_authority = SetAuthorityBuilder::new()
+        .authority(authority.pubkey())
+        .tree(tree_pda)
+        .new_authority(new_authority.pubkey())
+        .require_accept(true)
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree, ix_insert_leaf, ix_set_authority],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer, &authority],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.next_leaf_index, 1);
+    assert_eq!(my_account.authority, authority.pubkey());
+    assert_eq!(my_account.pending_authority, new_authority.pubkey());
+
+    // neither the payer nor the pending authority can insert before the transfer is accepted
+    for signer in [&shared.context.payer, &new_authority] {
+        let ix_insert_leaf = InsertLeafBuilder::new()
+            .authority(signer.pubkey())
+            .tree(tree_pda)
+            .leaf([2; 32])
+            .instruction();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix_insert_leaf],
+            Some(&shared.context.payer.pubkey()),
+            &[&shared.context.payer, signer],
+            shared.context.last_blockhash,
+        );
+        let error = shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(12)
+            ))
+        );
+    }
+
+    let ix_accept_authority = AcceptAuthorityBuilder::new()
+        .new_authority(new_authority.pubkey())
+        .tree(tree_pda)
+        .instruction();
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(new_authority.pubkey())
+        .tree(tree_pda)
+        .leaf([2; 32])
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_accept_authority, ix_insert_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer, &new_authority],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.next_leaf_index, 2);
+    assert_eq!(my_account.authority, new_authority.pubkey());
+    assert_eq!(my_account.pending_authority, Pubkey::default());
+
+    // the previous authority lost access
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(authority.pubkey())
+        .tree(tree_pda)
+        .leaf([3; 32])
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_insert_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer, &authority],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(12)
+        ))
+    );
+}
+
+#[tokio::test]
+async fn delegated_inserts() {
+    let mut shared = get_context().await;
+    let delegate = Keypair::new();
+
+    let ix_add_delegate = AddDelegateBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .delegate(delegate.pubkey())
+        .instruction();
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(delegate.pubkey())
+        .tree(shared.tree_pda)
+        .leaf([1; 32])
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_add_delegate, ix_insert_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer, &delegate],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.next_leaf_index, 1);
+    assert_eq!(my_account.delegates[0], delegate.pubkey());
+
+    // delegates can only insert
+    let ix_remove_leaf = RemoveLeafBuilder::new()
+        .authority(delegate.pubkey())
+        .tree(shared.tree_pda)
+        .index(0)
+        .previous_leaf([1; 32])
+        .instruction();
+    let ix_remove_delegate = RemoveDelegateBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .delegate(delegate.pubkey())
+        .instruction();
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(delegate.pubkey())
+        .tree(shared.tree_pda)
+        .leaf([2; 32])
+        .instruction();
+    let invalid_cases = [
+        (vec![ix_remove_leaf], true, 0, 12),
+        (vec![ix_remove_delegate.clone(), ix_insert_leaf], true, 1, 12),
+        (vec![ix_remove_delegate.clone(), ix_remove_delegate], false, 1, 14),
+    ];
+    for (ixs, delegate_signs, failing_ix, code) in invalid_cases {
+        let signers: &[&Keypair] = if delegate_signs {
+            &[&shared.context.payer, &delegate]
+        } else {
+            &[&shared.context.payer]
+        };
+        let tx = Transaction::new_signed_with_payer(
+            &ixs,
+            Some(&shared.context.payer.pubkey()),
+            signers,
+            shared.context.last_blockhash,
+        );
+        let error = shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                index,
+                InstructionError::Custom(error_code)
+            )) if index == failing_ix && error_code == code
+        );
+    }
+
+    // the first delegate still holds a slot, so only seven more fit
+    let ixs: Vec<Instruction> = (0..9)
+        .map(|_| {
+            AddDelegateBuilder::new()
+                .authority(shared.context.payer.pubkey())
+                .tree(shared.tree_pda)
+                .delegate(Keypair::new().pubkey())
+                .instruction()
+        })
+        .collect();
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            7,
+            InstructionError::Custom(13)
+        ))
+    );
+}
+
+#[tokio::test]
+async fn close_tree() {
+    let mut shared = get_context().await;
+    let destination = Keypair::new();
+
+    let build_remove_leaf_ix = |index: u32, previous_leaf: [u8; 32]| {
+        RemoveLeafBuilder::new()
+            .authority(shared.context.payer.pubkey())
+            .tree(shared.tree_pda)
+            .index(index)
+            .previous_leaf(previous_leaf)
+            .instruction()
+    };
+    let build_close_tree_ix = || {
+        CloseTreeBuilder::new()
+            .authority(shared.context.payer.pubkey())
+            .tree(shared.tree_pda)
+            .destination(destination.pubkey())
+            .require_empty(true)
+            .instruction()
+    };
+
+    let ix_insert_leaves = InsertLeavesBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .leaves(vec![[1; 32], [2; 32]])
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_insert_leaves, build_remove_leaf_ix(0, [1; 32])],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    // one leaf is still there
+    let tx = Transaction::new_signed_with_payer(
+        &[build_close_tree_ix()],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(15)
+        ))
+    );
+
+    let tree_lamports = shared
+        .context
+        .banks_client
+        .get_balance(shared.tree_pda)
+        .await
+        .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[build_remove_leaf_ix(1, [2; 32]), build_close_tree_ix()],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount");
+    assert!(account.is_none());
+    let destination_lamports = shared
+        .context
+        .banks_client
+        .get_balance(destination.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(destination_lamports, tree_lamports);
+
+    // the closed address is no longer a tree
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .leaf([3; 32])
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_insert_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(4)
+        ))
+    );
+}
+
+#[tokio::test]
+async fn close_unfinished_tree() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+    let attacker = Keypair::new();
+    let destination = Keypair::new();
+
+    // only the first of the calls creating the tree
+    let (tree_pda, _bump) = MerkleTree::find_pda(&payer, 1);
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(payer)
+        .tree(tree_pda)
+        .max_depth(10)
+        .tree_id(1)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .root_history_size(0)
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+    let tree_lamports = shared
+        .context
+        .banks_client
+        .get_balance(tree_pda)
+        .await
+        .unwrap();
+
+    let build_close_tree_ix = |authority: Pubkey| {
+        CloseTreeBuilder::new()
+            .authority(authority)
+            .tree(tree_pda)
+            .destination(destination.pubkey())
+            .require_empty(true)
+            .instruction()
+    };
+    // only the payer can close it
+    let tx = Transaction::new_signed_with_payer(
+        &[build_close_tree_ix(attacker.pubkey())],
+        Some(&payer),
+        &[&shared.context.payer, &attacker],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(12)
+        ))
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[build_close_tree_ix(payer)],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount");
+    assert!(account.is_none());
+    let destination_lamports = shared
+        .context
+        .banks_client
+        .get_balance(destination.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(destination_lamports, tree_lamports);
+}
+
+#[tokio::test]
+async fn close_unfinished_concurrent_tree() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+    let destination = Keypair::new();
+
+    // 65,028 bytes, the first of 7 calls
+    let (tree_pda, _bump) = ConcurrentMerkleTree::find_pda(&payer, 1);
+    let ix_create_tree = CreateConcurrentTreeBuilder::new()
+        .payer(payer)
+        .tree(tree_pda)
+        .max_depth(30)
+        .tree_id(1)
+        .change_log_size(64)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .canopy_depth(0)
+        .instruction();
+    let ix_close_tree = CloseTreeBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .destination(destination.pubkey())
+        .require_empty(true)
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&ix_create_tree),
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+    let tree_lamports = shared
+        .context
+        .banks_client
+        .get_balance(tree_pda)
+        .await
+        .unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_close_tree],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount");
+    assert!(account.is_none());
+    let destination_lamports = shared
+        .context
+        .banks_client
+        .get_balance(destination.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(destination_lamports, tree_lamports);
+
+    // the address can be created again from scratch
+    let tx = Transaction::new_signed_with_payer(
+        &vec![ix_create_tree; 7],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = ConcurrentMerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.authority, payer);
+    assert_eq!(my_account.next_leaf_index, 0);
+}
+
+#[tokio::test]
+async fn close_other_tree_kinds() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+    let destination = Keypair::new();
+    let leaf = keccak256(b"First");
+
+    let (incremental_pda, _bump) = IncrementalMerkleTree::find_pda(&payer, 0);
+    let (concurrent_pda, _bump) = ConcurrentMerkleTree::find_pda(&payer, 0);
+    let build_close_tree_ix = |tree: Pubkey, require_empty: bool| {
+        CloseTreeBuilder::new()
+            .authority(payer)
+            .tree(tree)
+            .destination(destination.pubkey())
+            .require_empty(require_empty)
+            .instruction()
+    };
+
+    let ixs = [
+        CreateIncrementalTreeBuilder::new()
+            .payer(payer)
+            .tree(incremental_pda)
+            .max_depth(3)
+            .tree_id(0)
+            .hash_kind(HashKind::Sha256)
+            .domain_separated(false)
+            .instruction(),
+        AppendLeafBuilder::new()
+            .authority(payer)
+            .tree(incremental_pda)
+            .leaf(leaf)
+            .instruction(),
+        CreateConcurrentTreeBuilder::new()
+            .payer(payer)
+            .tree(concurrent_pda)
+            .max_depth(3)
+            .tree_id(0)
+            .change_log_size(4)
+            .hash_kind(HashKind::Sha256)
+            .domain_separated(false)
+            .canopy_depth(0)
+            .instruction(),
+        AppendConcurrentLeafBuilder::new()
+            .authority(payer)
+            .tree(concurrent_pda)
+            .leaf(leaf)
+            .instruction(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    // an incremental tree cannot remove its leaves, so it is never empty again
+    let tx = Transaction::new_signed_with_payer(
+        &[build_close_tree_ix(incremental_pda, true)],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(15)
+        ))
+    );
+
+    // a concurrent tree is empty once its leaves are replaced with the empty value
+    let mut tree = OffchainMerkleTree::new(Sha256Hasher, 3, true);
+    tree.insert_leaf(leaf).unwrap();
+    let ix_remove_leaf = ReplaceConcurrentLeafBuilder::new()
+        .authority(payer)
+        .tree(concurrent_pda)
+        .root(tree.nodes[0])
+        .previous_leaf(leaf)
+        .new_leaf(EMPTY_LEAF)
+        .index(0)
+        .proof(proof_from_nodes(&Sha256Hasher, &tree.nodes, 3, 0))
+        .instruction();
+
+    let mut tree_lamports = 0;
+    for tree_pda in [incremental_pda, concurrent_pda] {
+        tree_lamports += shared
+            .context
+            .banks_client
+            .get_balance(tree_pda)
+            .await
+            .unwrap();
+    }
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            build_close_tree_ix(incremental_pda, false),
+            ix_remove_leaf,
+            build_close_tree_ix(concurrent_pda, true),
+        ],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    for tree_pda in [incremental_pda, concurrent_pda] {
+        let account = shared
+            .context
+            .banks_client
+            .get_account(tree_pda)
+            .await
+            .expect("Unable get acount");
+        assert!(account.is_none());
+    }
+    let destination_lamports = shared
+        .context
+        .banks_client
+        .get_balance(destination.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(destination_lamports, tree_lamports);
+}
+
+#[tokio::test]
+async fn grow_tree() {
+    let mut shared = get_context().await;
+    let new_max_depth = 9;
+
+    let leaves = [keccak256(b"First"), keccak256(b"Second"), keccak256(b"Third")];
+    let ix_insert_leaves = InsertLeavesBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .leaves(leaves.to_vec())
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_insert_leaves],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+    let account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let old_root = MerkleTree::from_bytes(&account.data).unwrap().nodes[0];
+
+    // Growing from depth 3 to 9 adds about 32 KiB, so it takes several calls.
+    let ix_grow_tree = GrowTreeBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .payer(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .new_max_depth(new_max_depth)
+        .instruction();
+    let mut calls = 0;
+    let my_account = loop {
+        let blockhash = shared.context.get_new_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&ix_grow_tree),
+            Some(&shared.context.payer.pubkey()),
+            &[&shared.context.payer],
+            blockhash,
+        );
+        shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap();
+        calls += 1;
+
+        let account = shared
+            .context
+            .banks_client
+            .get_account(shared.tree_pda)
+            .await
+            .expect("Unable get acount")
+            .unwrap();
+        let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+        if my_account.max_depth == new_max_depth {
+            break my_account;
+        }
+        // leaves stay usable while the account is growing
+        assert_eq!(my_account.nodes[0], old_root);
+
+        // and the next calls must ask for the same depth
+        if calls == 1 {
+            let ix_grow_less = GrowTreeBuilder::new()
+                .authority(shared.context.payer.pubkey())
+                .payer(shared.context.payer.pubkey())
+                .tree(shared.tree_pda)
+                .new_max_depth(4)
+                .instruction();
+            let tx = Transaction::new_signed_with_payer(
+                &[ix_grow_less],
+                Some(&shared.context.payer.pubkey()),
+                &[&shared.context.payer],
+                blockhash,
+            );
+            let error = shared
+                .context
+                .banks_client
+                .process_transaction(tx)
+                .await
+                .unwrap_err();
+            assert_matches!(
+                error,
+                BanksClientError::TransactionError(TransactionError::InstructionError(
+                    0,
+                    InstructionError::InvalidArgument
+                ))
+            );
+        }
+    };
+    assert_eq!(calls, 4);
+    assert_eq!(my_account.next_leaf_index, 3);
+    assert_eq!(my_account.nodes.len(), (1 << (new_max_depth + 1)) - 1);
+
+    // the old tree is the left-most subtree, under new parents with empty siblings
+    let zero_hashes = Sha256Hasher.zero_hashes(new_max_depth);
+    let mut expected_root = old_root;
+    for height in 3..new_max_depth {
+        expected_root = Sha256Hasher.hash_nodes(&expected_root, &zero_hashes[height as usize]);
+    }
+    assert_eq!(my_account.nodes[0], expected_root);
+    for (index, leaf) in leaves.iter().enumerate() {
+        assert_eq!(my_account.nodes[(1 << new_max_depth) - 1 + index], *leaf);
+    }
+
+    // existing indexes still verify and new leaves go after them
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .leaf([4; 32])
+        .instruction();
+    let ix_verify_leaf = VerifyLeafBuilder::new()
+        .tree(shared.tree_pda)
+        .leaf(leaves[1])
+        .index(1)
+        .proof(proof_from_nodes(&my_account.hash_kind, &my_account.nodes, new_max_depth, 1))
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_verify_leaf, ix_insert_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    // the depth can only increase
+    let ix_grow_tree = GrowTreeBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .payer(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .new_max_depth(new_max_depth - 1)
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_grow_tree],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(6)
+        ))
+    );
+}
+
+#[tokio::test]
+async fn keccak_tree() {
+    let mut shared = get_context().await;
+
+    let (tree_pda, _bump) = MerkleTree::find_pda(&shared.context.payer.pubkey(), 1);
+    let leaves = [keccak256(b"First"), keccak256(b"Second"), keccak256(b"Third")];
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .max_depth(3)
+        .tree_id(1)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Keccak256)
+        .domain_separated(false)
+        .root_history_size(0)
+        .instruction();
+    let ix_insert_leaves = InsertLeavesBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .leaves(leaves.to_vec())
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree, ix_insert_leaves],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.hash_kind, HashKind::Keccak256);
+    // the parent of the first two leaves is keccak256(left || right)
+    assert_eq!(my_account.nodes[3], keccak256(&[leaves[0], leaves[1]].concat()));
+
+    let mut tree = OffchainMerkleTree::new(HashKind::Keccak256, 3, false);
+    tree.insert_leaves(&leaves).unwrap();
+    assert_eq!(tree.nodes, my_account.nodes);
+
+    let ix_verify_leaf = VerifyLeafBuilder::new()
+        .tree(tree_pda)
+        .leaf(leaves[2])
+        .index(2)
+        .proof(proof_from_nodes(&my_account.hash_kind, &my_account.nodes, 3, 2))
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_verify_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn poseidon_tree() {
+    let mut shared = get_context().await;
+
+    let (tree_pda, _bump) = MerkleTree::find_pda(&shared.context.payer.pubkey(), 1);
+    // field elements 1, 2 and 3 as big-endian bytes
+    let leaves: Vec<[u8; 32]> = (1..=3u8)
+        .map(|i| {
+            let mut leaf = [0; 32];
+            leaf[31] = i;
+            leaf
+        })
+        .collect();
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .max_depth(3)
+        .tree_id(1)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Poseidon)
+        .domain_separated(false)
+        .root_history_size(0)
+        .instruction();
+    let ix_insert_leaves = InsertLeavesBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .leaves(leaves.clone())
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree, ix_insert_leaves],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.hash_kind, HashKind::Poseidon);
+    // circom's poseidon([1, 2])
+    assert_eq!(
+        hex::encode(my_account.nodes[3]),
+        "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+    );
+
+    let mut tree = OffchainMerkleTree::new(HashKind::Poseidon, 3, false);
+    tree.insert_leaves(&leaves).unwrap();
+    assert_eq!(tree.nodes, my_account.nodes);
+
+    let ix_verify_leaf = VerifyLeafBuilder::new()
+        .tree(tree_pda)
+        .leaf(leaves[2])
+        .index(2)
+        .proof(proof_from_nodes(&my_account.hash_kind, &my_account.nodes, 3, 2))
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_verify_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn domain_separated_tree() {
+    let mut shared = get_context().await;
+    let hasher = DomainSeparated(HashKind::Sha256);
+
+    let (tree_pda, _bump) = MerkleTree::find_pda(&shared.context.payer.pubkey(), 1);
+    let leaves = [keccak256(b"First"), keccak256(b"Second"), keccak256(b"Third")];
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .max_depth(3)
+        .tree_id(1)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(true)
+        .root_history_size(0)
+        .instruction();
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .leaf(leaves[0])
+        .instruction();
+    let ix_insert_leaves = InsertLeavesBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .leaves(leaves[1..].to_vec())
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree, ix_insert_leaf, ix_insert_leaves],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert!(my_account.domain_separated);
+
+    let mut tree = OffchainMerkleTree::new(hasher, 3, false);
+    tree.insert_leaves(&leaves).unwrap();
+    assert_eq!(tree.nodes, my_account.nodes);
+    // leaves are stored hashed with the 0x00 prefix
+    assert_eq!(my_account.nodes[7], Sha256Hasher.hashv(&[&[0], &leaves[0]]));
+
+    let proof = proof_from_nodes(&hasher, &my_account.nodes, 3, 1);
+    assert!(verify_proof(&hasher, &my_account.nodes[0], &leaves[1], 1, &proof, 3));
+    // neither the stored leaf node nor a plain SHA-256 proof verifies
+    assert!(!verify_proof(&hasher, &my_account.nodes[0], &my_account.nodes[8], 1, &proof, 3));
+    assert!(!verify_proof(&Sha256Hasher, &my_account.nodes[0], &leaves[1], 1, &proof, 3));
+
+    let ix_replace_leaf = ReplaceLeafBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(tree_pda)
+        .index(1)
+        .previous_leaf(leaves[1])
+        .new_leaf(keccak256(b"Amended"))
+        .instruction();
+    let ix_verify_leaf = VerifyLeafBuilder::new()
+        .tree(tree_pda)
+        .leaf(leaves[2])
+        .index(2)
+        .proof(proof_from_nodes(&hasher, &my_account.nodes, 3, 2))
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_verify_leaf, ix_replace_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    // an internal node presented as a leaf is rejected on-chain
+    let ix_verify_leaf = VerifyLeafBuilder::new()
+        .tree(tree_pda)
+        .leaf(my_account.nodes[3])
+        .index(0)
+        .proof(proof_from_nodes(&hasher, &my_account.nodes, 3, 0))
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_verify_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(7)
+        ))
+    );
+}
+
+#[tokio::test]
+async fn read_tree_account() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+
+    let (tree_pda, _bump) = MerkleTree::find_pda(&payer, 1);
+    let leaves: Vec<[u8; 32]> = (0..5u8).map(|i| keccak256(&[i])).collect();
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(payer)
+        .tree(tree_pda)
+        .max_depth(3)
+        .tree_id(1)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(true)
+        .root_history_size(0)
+        .instruction();
+    let ix_insert_leaves = InsertLeavesBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .leaves(leaves.clone())
+        .instruction();
+    let ix_remove_leaf = RemoveLeafBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .index(2)
+        .previous_leaf(leaves[2])
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree, ix_insert_leaves, ix_remove_leaf],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    let hasher = my_account.hasher();
+    assert_eq!(
+        hasher,
+        TreeHasher {
+            hash_kind: HashKind::Sha256,
+            domain_separated: true
+        }
+    );
+    assert_eq!(my_account.capacity(), 8);
+    assert!(!my_account.is_full());
+    assert_eq!(my_account.root(), my_account.nodes[0]);
+    assert_eq!(my_account.leaves().len(), 5);
+    assert_eq!(my_account.leaf(2), EMPTY_LEAF);
+    assert_eq!(my_account.leaves()[4], hasher.hash_leaf(&leaves[4]));
+
+    let mut tree = OffchainMerkleTree::new(DomainSeparated(HashKind::Sha256), 3, false);
+    tree.insert_leaves(&leaves).unwrap();
+    tree.remove_leaf(2, leaves[2]).unwrap();
+    assert_eq!(my_account.to_offchain_tree().unwrap().nodes, tree.nodes);
+    assert!(my_account.merkle_proof(1, leaves[1]).verify());
+    assert!(!my_account.merkle_proof(2, leaves[2]).verify());
+
+    // proofs read from the fetched account verify on-chain
+    let instructions: Vec<Instruction> = [0, 1, 3, 4]
+        .into_iter()
+        .map(|index| {
+            VerifyLeafBuilder::new()
+                .tree(tree_pda)
+                .leaf(leaves[index as usize])
+                .index(index)
+                .proof(my_account.proof(index))
+                .instruction()
+        })
+        .collect();
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+}
+
+// root of the full tree over `leaves`, hashing every level
+fn full_tree_root(hasher: &impl Hasher, leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hasher.hash_nodes(&pair[0], &pair[1]))
+            .collect();
+    }
+    level[0]
+}
+
+#[tokio::test]
+async fn empty_subtree_hashes() {
+    let mut shared = get_context().await;
+    let zero_hashes = Sha256Hasher.zero_hashes(3);
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    // a new tree stores no hashes, its root is the empty subtree of full height
+    assert!(my_account.nodes.iter().all(|node| *node == EMPTY_LEAF));
+    let root = root_from_nodes(&my_account.hash_kind, &my_account.nodes, 3);
+    assert_eq!(root, zero_hashes[3]);
+    assert_eq!(root, full_tree_root(&Sha256Hasher, &[EMPTY_LEAF; 8]));
+
+    let leaves = [[1; 32], [2; 32], [3; 32]];
+    let ix_insert_leaves = InsertLeavesBuilder::new()
+        .authority(shared.context.payer.pubkey())
+        .tree(shared.tree_pda)
+        .leaves(leaves.to_vec())
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_insert_leaves],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(shared.tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    // same root as the full tree with the free slots set to the empty leaf
+    let mut padded = [EMPTY_LEAF; 8];
+    padded[..3].copy_from_slice(&leaves);
+    assert_eq!(my_account.nodes[0], full_tree_root(&Sha256Hasher, &padded));
+
+    let proof = proof_from_nodes(&my_account.hash_kind, &my_account.nodes, 3, 2);
+    assert_eq!(proof[0], zero_hashes[0]);
+    assert_eq!(proof[2], zero_hashes[2]);
+    let ix_verify_leaf = VerifyLeafBuilder::new()
+        .tree(shared.tree_pda)
+        .leaf(leaves[2])
+        .index(2)
+        .proof(proof)
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_verify_leaf],
+        Some(&shared.context.payer.pubkey()),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn incremental_tree() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+    let max_depth = 20;
+
+    let (tree_pda, _bump) = IncrementalMerkleTree::find_pda(&payer, 0);
+    let leaves = [keccak256(b"First"), keccak256(b"Second"), keccak256(b"Third")];
+    let mut ixs = vec![CreateIncrementalTreeBuilder::new()
+        .payer(payer)
+        .tree(tree_pda)
+        .max_depth(max_depth)
+        .tree_id(0)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .instruction()];
+    for leaf in leaves {
+        ixs.push(
+            AppendLeafBuilder::new()
+                .authority(payer)
+                .tree(tree_pda)
+                .leaf(leaf)
+                .instruction(),
+        );
+    }
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    // only the frontier is stored, one node per level
+    assert!(account.data.len() < 1024);
+    let my_account = IncrementalMerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.next_leaf_index, 3);
+    assert_eq!(my_account.filled_subtrees.len(), max_depth as usize);
+
+    // same root as a full tree holding the leaves, whose other subtrees are empty
+    let zero_hashes = Sha256Hasher.zero_hashes(max_depth);
+    let mut expected_root =
+        full_tree_root(&Sha256Hasher, &[leaves[0], leaves[1], leaves[2], EMPTY_LEAF]);
+    for zero_hash in &zero_hashes[2..max_depth as usize] {
+        expected_root = Sha256Hasher.hash_nodes(&expected_root, zero_hash);
+    }
+    assert_eq!(my_account.root, expected_root);
+
+    // a proof rebuilt from the appended leaves verifies against the stored root
+    let mut proof = vec![zero_hashes[0], Sha256Hasher.hash_nodes(&leaves[0], &leaves[1])];
+    proof.extend_from_slice(&zero_hashes[2..max_depth as usize]);
+    assert!(verify_proof(&Sha256Hasher, &my_account.root, &leaves[2], 2, &proof, max_depth));
+
+    // a full tree of depth 1, and one with another authority
+    let (small_tree_pda, _bump) = IncrementalMerkleTree::find_pda(&payer, 1);
+    let (other_tree_pda, _bump) = IncrementalMerkleTree::find_pda(&payer, 2);
+    let mut ixs = vec![
+        CreateIncrementalTreeBuilder::new()
+            .payer(payer)
+            .tree(small_tree_pda)
+            .max_depth(1)
+            .tree_id(1)
+            .hash_kind(HashKind::Sha256)
+            .domain_separated(false)
+            .instruction(),
+        CreateIncrementalTreeBuilder::new()
+            .payer(payer)
+            .tree(other_tree_pda)
+            .max_depth(1)
+            .tree_id(2)
+            .authority(Keypair::new().pubkey())
+            .hash_kind(HashKind::Sha256)
+            .domain_separated(false)
+            .instruction(),
+    ];
+    for leaf in &leaves[..2] {
+        ixs.push(
+            AppendLeafBuilder::new()
+                .authority(payer)
+                .tree(small_tree_pda)
+                .leaf(*leaf)
+                .instruction(),
+        );
+    }
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let invalid_ixs = [
+        (
+            AppendLeafBuilder::new()
+                .authority(payer)
+                .tree(small_tree_pda)
+                .leaf(leaves[2])
+                .instruction(),
+            3,
+        ),
+        (
+            AppendLeafBuilder::new()
+                .authority(payer)
+                .tree(other_tree_pda)
+                .leaf(leaves[2])
+                .instruction(),
+            12,
+        ),
+        (
+            AppendLeafBuilder::new()
+                .authority(payer)
+                .tree(tree_pda)
+                .leaf(EMPTY_LEAF)
+                .instruction(),
+            10,
+        ),
+        // the two tree kinds cannot be mistaken for each other
+        (
+            AppendLeafBuilder::new()
+                .authority(payer)
+                .tree(shared.tree_pda)
+                .leaf(leaves[2])
+                .instruction(),
+            1,
+        ),
+        (
+            InsertLeafBuilder::new()
+                .authority(payer)
+                .tree(tree_pda)
+                .leaf(leaves[2])
+                .instruction(),
+            1,
+        ),
+        (
+            CreateIncrementalTreeBuilder::new()
+                .payer(payer)
+                .tree(IncrementalMerkleTree::find_pda(&payer, 3).0)
+                .max_depth(33)
+                .tree_id(3)
+                .hash_kind(HashKind::Sha256)
+                .domain_separated(false)
+                .instruction(),
+            6,
+        ),
+    ];
+    for (ix, code) in invalid_ixs {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer),
+            &[&shared.context.payer],
+            shared.context.last_blockhash,
+        );
+        let error = shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(error_code)
+            )) if error_code == code
+        );
+    }
+}
+
+#[tokio::test]
+async fn root_history() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+
+    let (tree_pda, _bump) = MerkleTree::find_pda(&payer, 1);
+    let leaves = [
+        keccak256(b"First"),
+        keccak256(b"Second"),
+        keccak256(b"Third"),
+        keccak256(b"Fourth"),
+    ];
+    let ixs = [
+        CreateTreeBuilder::new()
+            .payer(payer)
+            .tree(tree_pda)
+            .max_depth(3)
+            .tree_id(1)
+            .allow_leaf_reuse(false)
+            .hash_kind(HashKind::Sha256)
+            .domain_separated(false)
+            .root_history_size(2)
+            .instruction(),
+        InsertLeafBuilder::new()
+            .authority(payer)
+            .tree(tree_pda)
+            .leaf(leaves[0])
+            .instruction(),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    // a proof built before other leaves are inserted
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    let first_root = my_account.nodes[0];
+    let stale_proof = proof_from_nodes(&my_account.hash_kind, &my_account.nodes, 3, 0);
+    let ix_verify_leaf = VerifyLeafBuilder::new()
+        .tree(tree_pda)
+        .leaf(leaves[0])
+        .index(0)
+        .proof(stale_proof)
+        .instruction();
+
+    // with a history of 2 roots, the proof keeps verifying for 2 more inserts
+    for (inserted, leaf) in leaves[1..].iter().enumerate() {
+        let blockhash = shared.context.get_new_latest_blockhash().await.unwrap();
+        let ix_insert_leaf = InsertLeafBuilder::new()
+            .authority(payer)
+            .tree(tree_pda)
+            .leaf(*leaf)
+            .instruction();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix_insert_leaf, ix_verify_leaf.clone()],
+            Some(&payer),
+            &[&shared.context.payer],
+            blockhash,
+        );
+        let result = shared.context.banks_client.process_transaction(tx).await;
+        if inserted < 2 {
+            result.unwrap();
+        } else {
+            assert_matches!(
+                result.unwrap_err(),
+                BanksClientError::TransactionError(TransactionError::InstructionError(
+                    1,
+                    InstructionError::Custom(7)
+                ))
+            );
+        }
+    }
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    // the last insert was rolled back, so the ring holds the roots replaced by the
+    // two inserts before it, the next slot being the older one
+    assert_eq!(my_account.next_leaf_index, 3);
+    assert_eq!(my_account.root_history.len(), 2);
+    assert_eq!(my_account.root_history[1], first_root);
+    assert_eq!(my_account.root_history_index, 1);
+
+    // removing a leaf clears the history, so no older root verifies it anymore
+    let ix_verify_leaf = VerifyLeafBuilder::new()
+        .tree(tree_pda)
+        .leaf(leaves[1])
+        .index(1)
+        .proof(my_account.proof(1))
+        .instruction();
+    let ix_remove_leaf = RemoveLeafBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .index(1)
+        .previous_leaf(leaves[1])
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_verify_leaf.clone(), ix_remove_leaf],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_verify_leaf],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(7)
+        ))
+    );
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.root_history, vec![EMPTY_LEAF; 2]);
+
+    let (tree_pda, _bump) = MerkleTree::find_pda(&payer, 2);
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(payer)
+        .tree(tree_pda)
+        .max_depth(3)
+        .tree_id(2)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .root_history_size(65)
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(16)
+        ))
+    );
+}
+
+#[tokio::test]
+async fn concurrent_tree() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+
+    let (tree_pda, _bump) = ConcurrentMerkleTree::find_pda(&payer, 0);
+    let leaves: Vec<[u8; 32]> = (1..=8u8).map(|i| keccak256(&[i])).collect();
+    let append = |leaf: [u8; 32]| {
+        AppendConcurrentLeafBuilder::new()
+            .authority(payer)
+            .tree(tree_pda)
+            .leaf(leaf)
+            .instruction()
+    };
+    let mut ixs = vec![CreateConcurrentTreeBuilder::new()
+        .payer(payer)
+        .tree(tree_pda)
+        .max_depth(3)
+        .tree_id(0)
+        .change_log_size(4)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .canopy_depth(0)
+        .instruction()];
+    ixs.extend(leaves[..3].iter().map(|leaf| append(*leaf)));
+
+    // the same changes on an off-chain tree
+    let mut tree = OffchainMerkleTree::new(Sha256Hasher, 3, true);
+    for leaf in &leaves[..3] {
+        tree.insert_leaf(*leaf).unwrap();
+    }
+    let stale_root = tree.nodes[0];
+    let stale_proofs: Vec<Vec<[u8; 32]>> =
+        (0..3).map(|index| proof_from_nodes(&Sha256Hasher, &tree.nodes, 3, index)).collect();
+    let (first, second) = (keccak256(b"First"), keccak256(b"Second"));
+
+    // two replaces built against the same root, then an append
+    ixs.push(
+        ReplaceConcurrentLeafBuilder::new()
+            .authority(payer)
+            .tree(tree_pda)
+            .root(stale_root)
+            .previous_leaf(leaves[0])
+            .new_leaf(first)
+            .index(0)
+            .proof(stale_proofs[0].clone())
+            .instruction(),
+    );
+    ixs.push(
+        ReplaceConcurrentLeafBuilder::new()
+            .authority(payer)
+            .tree(tree_pda)
+            .root(stale_root)
+            .previous_leaf(leaves[1])
+            .new_leaf(second)
+            .index(1)
+            .proof(stale_proofs[1].clone())
+            .instruction(),
+    );
+    ixs.push(append(leaves[3]));
+    ixs.push(
+        VerifyConcurrentLeafBuilder::new()
+            .tree(tree_pda)
+            .root(stale_root)
+            .leaf(leaves[2])
+            .index(2)
+            .proof(stale_proofs[2].clone())
+            .instruction(),
+    );
+    tree.replace_leaf(0, leaves[0], first).unwrap();
+    tree.replace_leaf(1, leaves[1], second).unwrap();
+    tree.insert_leaf(leaves[3]).unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = ConcurrentMerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.sequence_number, 6);
+    assert_eq!(my_account.next_leaf_index, 4);
+    assert_eq!(my_account.change_logs.len(), 4);
+    assert_eq!(my_account.change_logs[my_account.active_index as usize].root, tree.nodes[0]);
+
+    // removing a leaf through the stale root, then filling the tree
+    let mut ixs = vec![ReplaceConcurrentLeafBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .root(stale_root)
+        .previous_leaf(leaves[2])
+        .new_leaf(EMPTY_LEAF)
+        .index(2)
+        .proof(stale_proofs[2].clone())
+        .instruction()];
+    ixs.extend(leaves[4..].iter().map(|leaf| append(*leaf)));
+    tree.remove_leaf(2, leaves[2]).unwrap();
+    for leaf in &leaves[4..] {
+        tree.insert_leaf(*leaf).unwrap();
+    }
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = ConcurrentMerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.next_leaf_index, 8);
+    assert_eq!(my_account.change_logs[my_account.active_index as usize].root, tree.nodes[0]);
+
+    let current_root = tree.nodes[0];
+    let invalid_ixs = [
+        // leaf 0 is no longer the previous leaf given
+        (
+            ReplaceConcurrentLeafBuilder::new()
+                .authority(payer)
+                .tree(tree_pda)
+                .root(current_root)
+                .previous_leaf(leaves[0])
+                .new_leaf(second)
+                .index(0)
+                .proof(proof_from_nodes(&Sha256Hasher, &tree.nodes, 3, 0))
+                .instruction(),
+            7,
+        ),
+        // the removed leaf 2 holds the empty value, which is not a leaf
+        (
+            VerifyConcurrentLeafBuilder::new()
+                .tree(tree_pda)
+                .root(current_root)
+                .leaf(EMPTY_LEAF)
+                .index(2)
+                .proof(proof_from_nodes(&Sha256Hasher, &tree.nodes, 3, 2))
+                .instruction(),
+            7,
+        ),
+        // the stale root left the change log
+        (
+            VerifyConcurrentLeafBuilder::new()
+                .tree(tree_pda)
+                .root(stale_root)
+                .leaf(leaves[1])
+                .index(1)
+                .proof(stale_proofs[1].clone())
+                .instruction(),
+            7,
+        ),
+        (append(keccak256(b"Ninth")), 3),
+        (
+            AppendConcurrentLeafBuilder::new()
+                .authority(payer)
+                .tree(shared.tree_pda)
+                .leaf(leaves[0])
+                .instruction(),
+            1,
+        ),
+    ];
+    for (ix, code) in invalid_ixs {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer),
+            &[&shared.context.payer],
+            shared.context.last_blockhash,
+        );
+        let error = shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(error_code)
+            )) if error_code == code
+        );
+    }
+
+    // a change against a recent root whose leaf was changed since is rejected
+    let blockhash = shared.context.get_new_latest_blockhash().await.unwrap();
+    let ix_replace_leaf = ReplaceConcurrentLeafBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .root(current_root)
+        .previous_leaf(leaves[7])
+        .new_leaf(first)
+        .index(7)
+        .proof(proof_from_nodes(&Sha256Hasher, &tree.nodes, 3, 7))
+        .instruction();
+    let ix_replace_again = ReplaceConcurrentLeafBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .root(current_root)
+        .previous_leaf(leaves[7])
+        .new_leaf(second)
+        .index(7)
+        .proof(proof_from_nodes(&Sha256Hasher, &tree.nodes, 3, 7))
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_replace_leaf, ix_replace_again],
+        Some(&payer),
+        &[&shared.context.payer],
+        blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(9)
+        ))
+    );
+}
+
+#[tokio::test]
+async fn structured_events() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+    let (tree_pda, _bump) = MerkleTree::find_pda(&payer, 1);
+
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(payer)
+        .tree(tree_pda)
+        .max_depth(3)
+        .tree_id(1)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .root_history_size(0)
+        .instruction();
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .leaf([1; 32])
+        .instruction();
+    let ix_replace_leaf = ReplaceLeafBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .index(0)
+        .previous_leaf([1; 32])
+        .new_leaf([2; 32])
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree, ix_insert_leaf, ix_replace_leaf],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let result = shared
+        .context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .unwrap();
+    assert!(result.result.is_ok());
+    let logs = result.metadata.unwrap().log_messages;
+
+    let hasher = Sha256Hasher;
+    let zero_hashes = hasher.zero_hashes(3);
+    let mut inserted = [EMPTY_LEAF; 8];
+    inserted[0] = [1; 32];
+    let mut replaced = [EMPTY_LEAF; 8];
+    replaced[0] = [2; 32];
+    assert_eq!(
+        events_from_logs(&logs),
+        vec![
+            MerkleTreeEvent::TreeCreated(TreeCreated {
+                tree: tree_pda,
+                kind: TreeKind::Full,
+                max_depth: 3,
+                authority: payer,
+                root: zero_hashes[3],
+            }),
+            MerkleTreeEvent::LeafInserted(LeafInserted {
+                tree: tree_pda,
+                index: 0,
+                leaf: [1; 32],
+                root: full_tree_root(&hasher, &inserted),
+            }),
+            MerkleTreeEvent::LeafReplaced(LeafReplaced {
+                tree: tree_pda,
+                index: 0,
+                previous_leaf: [1; 32],
+                new_leaf: [2; 32],
+                root: full_tree_root(&hasher, &replaced),
+            }),
+        ]
+    );
+
+    // events logged by other programs are ignored
+    let foreign = vec![
+        format!("Program {} invoke [1]", system_program::ID),
+        "Program data: AAAA".to_string(),
+        format!("Program {} success", system_program::ID),
+    ];
+    assert!(events_from_logs(&foreign).is_empty());
+}
+
+#[tokio::test]
+async fn event_cpi() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+    let tree_pda = shared.tree_pda;
+
+    // with the event accounts, the event is emitted through a self-CPI
+    let ix_insert_leaf = InsertLeafBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .leaf([1; 32])
+        .add_remaining_accounts(&event_cpi_accounts())
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&ix_insert_leaf),
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    let my_account = MerkleTree::deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(my_account.next_leaf_index, 1);
+
+    let event = MerkleTreeEvent::LeafInserted(LeafInserted {
+        tree: tree_pda,
+        index: 0,
+        leaf: [1; 32],
+        root: root_from_nodes(&Sha256Hasher, &my_account.nodes, 3),
+    });
+
+    // EmitEvent only accepts the event authority, which only the program can sign for
+    let ix_emit_event = EmitEventBuilder::new()
+        .event_authority(payer)
+        .event(event.clone())
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_emit_event],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(12)
+        ))
+    );
+
+    // the event is read back from the inner instructions, in every RPC encoding
+    let mut ix_emitted = EmitEventBuilder::new()
+        .event_authority(find_event_authority().0)
+        .event(event.clone())
+        .instruction();
+    // the PDA signature is granted by the CPI, not the transaction
+    ix_emitted.accounts[0].is_signer = false;
+    let mut message = Message::new(&[ix_insert_leaf, ix_emitted], Some(&payer));
+    let emitted = message.instructions.pop().unwrap();
+    let transaction = VersionedTransactionWithStatusMeta {
+        transaction: VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(message),
+        },
+        meta: TransactionStatusMeta {
+            inner_instructions: Some(vec![InnerInstructions {
+                index: 0,
+                instructions: vec![InnerInstruction {
+                    instruction: emitted,
+                    stack_height: Some(2),
+                }],
+            }]),
+            ..TransactionStatusMeta::default()
+        },
+    };
+    for encoding in [
+        UiTransactionEncoding::Base64,
+        UiTransactionEncoding::Json,
+        UiTransactionEncoding::JsonParsed,
+    ] {
+        let encoded = transaction.clone().encode(encoding, Some(0), false).unwrap();
+        assert_eq!(events_from_transaction(&encoded), vec![event.clone()]);
+    }
+}
+
+#[tokio::test]
+async fn concurrent_tree_canopy() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+
+    let (tree_pda, _bump) = ConcurrentMerkleTree::find_pda(&payer, 1);
+    let create = |change_log_size: u8, canopy_depth: u8| {
+        CreateConcurrentTreeBuilder::new()
+            .payer(payer)
+            .tree(tree_pda)
+            .max_depth(3)
+            .tree_id(1)
+            .change_log_size(change_log_size)
+            .hash_kind(HashKind::Sha256)
+            .domain_separated(false)
+            .canopy_depth(canopy_depth)
+            .instruction()
+    };
+
+    // the change log keeps 1 to 64 entries
+    for change_log_size in [0, 65] {
+        let tx = Transaction::new_signed_with_payer(
+            &[create(change_log_size, 0)],
+            Some(&payer),
+            &[&shared.context.payer],
+            shared.context.last_blockhash,
+        );
+        let error = shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(18)
+            ))
+        );
+    }
+
+    // the canopy cannot be deeper than the tree
+    let tx = Transaction::new_signed_with_payer(
+        &[create(4, 4)],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(17)
+        ))
+    );
+
+    let leaves: Vec<[u8; 32]> = (1..=5u8).map(|i| keccak256(&[i])).collect();
+    let mut ixs = vec![create(4, 2)];
+    ixs.extend(leaves.iter().map(|leaf| {
+        AppendConcurrentLeafBuilder::new()
+            .authority(payer)
+            .tree(tree_pda)
+            .leaf(*leaf)
+            .instruction()
+    }));
+    let mut tree = OffchainMerkleTree::new(Sha256Hasher, 3, true);
+    for leaf in &leaves {
+        tree.insert_leaf(*leaf).unwrap();
+    }
+    let stale_root = tree.nodes[0];
+    let stale_proofs: Vec<Vec<[u8; 32]>> =
+        (0..5).map(|index| proof_from_nodes(&Sha256Hasher, &tree.nodes, 3, index)).collect();
+    let (first, second) = (keccak256(b"First"), keccak256(b"Second"));
+
+    // proofs trimmed to one sibling, the second change fast-forwarded past the first
+    ixs.push(
+        ReplaceConcurrentLeafBuilder::new()
+            .authority(payer)
+            .tree(tree_pda)
+            .root(stale_root)
+            .previous_leaf(leaves[1])
+            .new_leaf(first)
+            .index(1)
+            .proof(trim_proof(&stale_proofs[1], 2))
+            .instruction(),
+    );
+    ixs.push(
+        ReplaceConcurrentLeafBuilder::new()
+            .authority(payer)
+            .tree(tree_pda)
+            .root(stale_root)
+            .previous_leaf(leaves[4])
+            .new_leaf(second)
+            .index(4)
+            .proof(trim_proof(&stale_proofs[4], 2))
+            .instruction(),
+    );
+    // full proofs are still accepted
+    ixs.push(
+        VerifyConcurrentLeafBuilder::new()
+            .tree(tree_pda)
+            .root(stale_root)
+            .leaf(leaves[0])
+            .index(0)
+            .proof(stale_proofs[0].clone())
+            .instruction(),
+    );
+    tree.replace_leaf(1, leaves[1], first).unwrap();
+    tree.replace_leaf(4, leaves[4], second).unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = ConcurrentMerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.change_logs[my_account.active_index as usize].root, tree.nodes[0]);
+    // the two levels below the root, with empty subtrees left zeroed
+    assert_eq!(my_account.canopy_depth, 2);
+    assert_eq!(my_account.canopy, tree.nodes[1..7]);
+
+    let proof = proof_from_nodes(&Sha256Hasher, &tree.nodes, 3, 3);
+    assert_eq!(my_account.trim_proof(&proof), proof[..1].to_vec());
+    let blockhash = shared.context.get_new_latest_blockhash().await.unwrap();
+    let verify = |proof: Vec<[u8; 32]>| {
+        VerifyConcurrentLeafBuilder::new()
+            .tree(tree_pda)
+            .root(tree.nodes[0])
+            .leaf(leaves[3])
+            .index(3)
+            .proof(proof)
+            .instruction()
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[verify(my_account.trim_proof(&proof))],
+        Some(&payer),
+        &[&shared.context.payer],
+        blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    // a proof must reach the canopy
+    let tx = Transaction::new_signed_with_payer(
+        &[verify(Vec::new())],
+        Some(&payer),
+        &[&shared.context.payer],
+        blockhash,
+    );
+    let error = shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        error,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(7)
+        ))
+    );
+}
+
+#[tokio::test]
+async fn largest_concurrent_tree() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+
+    // max depth, change log size and canopy depth
+    let (tree_pda, _bump) = ConcurrentMerkleTree::find_pda(&payer, 2);
+    let ix_create_tree = CreateConcurrentTreeBuilder::new()
+        .payer(payer)
+        .tree(tree_pda)
+        .max_depth(30)
+        .tree_id(2)
+        .change_log_size(64)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .canopy_depth(17)
+        .instruction();
+    // 8,453,572 bytes allocated 10 KiB per call
+    let calls = vec![ix_create_tree; 826];
+    for ixs in calls.chunks(20) {
+        let blockhash = shared.context.get_new_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&payer),
+            &[&shared.context.payer],
+            blockhash,
+        );
+        shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap();
+    }
+
+    let leaves: Vec<[u8; 32]> = (1..=8u8).map(|i| keccak256(&[i])).collect();
+    let mut ixs: Vec<Instruction> = leaves
+        .iter()
+        .map(|leaf| {
+            AppendConcurrentLeafBuilder::new()
+                .authority(payer)
+                .tree(tree_pda)
+                .leaf(*leaf)
+                .instruction()
+        })
+        .collect();
+
+    // the leaves sit in the first depth-3 subtree, the rest of the proof is empty subtrees
+    let zero_hashes = Sha256Hasher.zero_hashes(30);
+    let mut tree = OffchainMerkleTree::new(Sha256Hasher, 3, true);
+    for leaf in &leaves {
+        tree.insert_leaf(*leaf).unwrap();
+    }
+    let proof = |tree: &OffchainMerkleTree<Sha256Hasher>, index: u32| {
+        [proof_from_nodes(&Sha256Hasher, &tree.nodes, 3, index), zero_hashes[3..30].to_vec()]
+            .concat()
+    };
+    let stale_proofs: Vec<Vec<[u8; 32]>> = (0..8).map(|index| proof(&tree, index)).collect();
+    let stale_root = Sha256Hasher.compute_root(&leaves[0], 0, &stale_proofs[0]);
+    let first = keccak256(b"First");
+
+    // one proof per transaction to stay under the packet size
+    ixs.push(
+        ReplaceConcurrentLeafBuilder::new()
+            .authority(payer)
+            .tree(tree_pda)
+            .root(stale_root)
+            .previous_leaf(leaves[1])
+            .new_leaf(first)
+            .index(1)
+            .proof(trim_proof(&stale_proofs[1], 17))
+            .instruction(),
+    );
+    ixs.push(
+        VerifyConcurrentLeafBuilder::new()
+            .tree(tree_pda)
+            .root(stale_root)
+            .leaf(leaves[6])
+            .index(6)
+            .proof(trim_proof(&stale_proofs[6], 17))
+            .instruction(),
+    );
+    tree.replace_leaf(1, leaves[1], first).unwrap();
+
+    for ixs in [&ixs[..8], &ixs[8..9], &ixs[9..]] {
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&payer),
+            &[&shared.context.payer],
+            shared.context.last_blockhash,
+        );
+        shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap();
+    }
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = ConcurrentMerkleTree::from_bytes(&account.data).unwrap();
+    assert_eq!(my_account.max_depth, 30);
+    assert_eq!(my_account.sequence_number, 9);
+    assert_eq!(my_account.next_leaf_index, 8);
+    assert_eq!(my_account.change_logs.len(), 64);
+    assert_eq!(my_account.canopy.len(), (1 << 18) - 2);
+    // only the paths of changed leaves are written
+    assert_eq!(my_account.canopy[1], EMPTY_LEAF);
+    assert_eq!(
+        my_account.change_logs[my_account.active_index as usize].root,
+        Sha256Hasher.compute_root(&first, 1, &proof(&tree, 1))
+    );
+}
+
+#[tokio::test]
+async fn verify_leaves() {
+    let mut shared = get_context().await;
+    let payer = shared.context.payer.pubkey();
+
+    let (tree_pda, _bump) = MerkleTree::find_pda(&payer, 1);
+    let leaves: Vec<[u8; 32]> = (0..10u8).map(|i| keccak256(&[i])).collect();
+    let ix_create_tree = CreateTreeBuilder::new()
+        .payer(payer)
+        .tree(tree_pda)
+        .max_depth(4)
+        .tree_id(1)
+        .allow_leaf_reuse(false)
+        .hash_kind(HashKind::Sha256)
+        .domain_separated(false)
+        .root_history_size(0)
+        .instruction();
+    let ix_insert_leaves = InsertLeavesBuilder::new()
+        .authority(payer)
+        .tree(tree_pda)
+        .leaves(leaves.clone())
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_create_tree, ix_insert_leaves],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let account = shared
+        .context
+        .banks_client
+        .get_account(tree_pda)
+        .await
+        .expect("Unable get acount")
+        .unwrap();
+    let my_account = MerkleTree::from_bytes(&account.data).unwrap();
+
+    let indices = vec![1, 2, 3, 7, 9];
+    let proven: Vec<[u8; 32]> = indices.iter().map(|i| leaves[*i as usize]).collect();
+    let multiproof = my_account.multiproof(&indices);
+    assert!(multiproof.len() < indices.len() * 4);
+    let verify = |indices: Vec<u32>, leaves: Vec<[u8; 32]>| {
+        VerifyLeavesBuilder::new()
+            .tree(tree_pda)
+            .indices(indices)
+            .leaves(leaves)
+            .proof(multiproof.clone())
+            .instruction()
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[verify(indices.clone(), proven.clone())],
+        Some(&payer),
+        &[&shared.context.payer],
+        shared.context.last_blockhash,
+    );
+    shared
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    // a wrong leaf, leaves out of order, and a leaf not inserted yet all fail
+    let mut wrong_leaf = proven.clone();
+    wrong_leaf[2] = leaves[4];
+    let mut unordered = indices.clone();
+    unordered.swap(0, 1);
+    let mut not_inserted = indices.clone();
+    not_inserted[4] = 11;
+    for ix in [
+        verify(indices.clone(), wrong_leaf),
+        verify(unordered, proven.clone()),
+        verify(not_inserted, proven.clone()),
+    ] {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer),
+            &[&shared.context.payer],
+            shared.context.last_blockhash,
+        );
+        let error = shared
+            .context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(7)
+            ))
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use merkle_tree_storage::{
+    types::HashKind, Hasher, MerkleProof, OffchainMerkleTree, TreeHasher, EMPTY_NODE,
+};
+
+fn leaf(i: u32) -> [u8; 32] {
+    HashKind::Keccak256.hashv(&[&i.to_le_bytes()])
+}
+
+fn proof(hasher: TreeHasher, index: u32) -> MerkleProof {
+    let mut tree = OffchainMerkleTree::new(hasher, 10, false);
+    for i in 0..5 {
+        tree.insert_leaf(leaf(i)).unwrap();
+    }
+    MerkleProof {
+        leaf: leaf(index),
+        index,
+        max_depth: 10,
+        siblings: tree.proof(index).unwrap(),
+        root: tree.root(),
+        hash_kind: hasher.hash_kind,
+        domain_separated: hasher.domain_separated,
+    }
+}
+
+fn hashers() -> Vec<TreeHasher> {
+    let mut hashers = Vec::new();
+    for hash_kind in [HashKind::Sha256, HashKind::Keccak256, HashKind::Poseidon] {
+        for domain_separated in [false, true] {
+            hashers.push(TreeHasher {
+                hash_kind,
+                domain_separated,
+            });
+        }
+    }
+    hashers
+}
+
+#[test]
+fn verify() {
+    for hasher in hashers() {
+        let proof = proof(hasher, 3);
+        assert!(proof.verify());
+
+        let mut wrong_leaf = proof.clone();
+        wrong_leaf.leaf = leaf(4);
+        assert!(!wrong_leaf.verify());
+
+        // same path bits, but outside the tree
+        let mut wrong_index = proof.clone();
+        wrong_index.index += 1 << 10;
+        assert!(!wrong_index.verify());
+
+        let mut wrong_scheme = proof.clone();
+        wrong_scheme.domain_separated = !hasher.domain_separated;
+        assert!(!wrong_scheme.verify());
+
+        // unused slots hold the empty value, which is never proven
+        let mut empty_slot = self::proof(hasher, 7);
+        empty_slot.leaf = EMPTY_NODE;
+        assert!(!empty_slot.verify());
+
+        let mut too_deep = proof.clone();
+        too_deep.siblings.resize(33, EMPTY_NODE);
+        assert!(!too_deep.verify());
+    }
+
+    // the parent of leaves 2 and 3 with the rest of their path gives the root, but
+    // the proof is one level short of the tree's depth
+    let hasher = TreeHasher {
+        hash_kind: HashKind::Sha256,
+        domain_separated: false,
+    };
+    let full = proof(hasher, 3);
+    let internal_node = MerkleProof {
+        leaf: hasher.hash_nodes(&leaf(2), &leaf(3)),
+        index: 1,
+        siblings: full.siblings[1..].to_vec(),
+        ..full.clone()
+    };
+    assert_eq!(
+        hasher.compute_root(&internal_node.leaf, 1, &internal_node.siblings),
+        full.root
+    );
+    assert!(!internal_node.verify());
+}
+
+#[test]
+fn binary_round_trips() {
+    for hasher in hashers() {
+        let proof = proof(hasher, 4);
+
+        let borsh = proof.try_to_vec().unwrap();
+        assert_eq!(MerkleProof::try_from_slice(&borsh).unwrap(), proof);
+
+        // of the 10 siblings, only the subtree over leaves 0 to 3 is not empty
+        let compact = proof.to_compact_bytes().unwrap();
+        assert_eq!(compact.len(), 70 + 2 + 32);
+        assert!(compact.len() < borsh.len());
+        assert_eq!(MerkleProof::from_compact_bytes(&compact).unwrap(), proof);
+
+        assert!(MerkleProof::from_compact_bytes(&compact[..compact.len() - 1]).is_err());
+        assert!(MerkleProof::from_compact_bytes(&[compact.as_slice(), &[0]].concat()).is_err());
+
+        // the depth is written once, for one sibling per level
+        let mut too_deep = proof.clone();
+        too_deep.siblings.resize(256, EMPTY_NODE);
+        assert!(too_deep.to_compact_bytes().is_err());
+        let mut too_short = proof.clone();
+        too_short.siblings.pop();
+        assert!(too_short.to_compact_bytes().is_err());
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_round_trips() {
+    use merkle_tree_storage::ByteEncoding;
+
+    let proof = proof(
+        TreeHasher {
+            hash_kind: HashKind::Sha256,
+            domain_separated: true,
+        },
+        2,
+    );
+
+    let json = serde_json::to_value(&proof).unwrap();
+    assert_eq!(json["leaf"], hex::encode(leaf(2)));
+    assert_eq!(json["max_depth"], 10);
+    assert_eq!(json["hash_kind"], "Sha256");
+    assert_eq!(serde_json::from_value::<MerkleProof>(json).unwrap(), proof);
+
+    let json = serde_json::to_value(proof.encoded(ByteEncoding::Base58)).unwrap();
+    assert_eq!(json["root"], bs58::encode(proof.root).into_string());
+    assert_eq!(serde_json::from_value::<MerkleProof>(json).unwrap(), proof);
+}
@@ -0,0 +1,151 @@
+use merkle_tree_storage::{
+    errors::MerkleTreeStorageError, types::HashKind, verify_multiproof, verify_proof,
+    DomainSeparated, Hasher, OffchainMerkleTree, EMPTY_NODE,
+};
+
+fn leaf(i: u32) -> [u8; 32] {
+    HashKind::Keccak256.hashv(&[&i.to_le_bytes()])
+}
+
+#[test]
+fn proofs_match_root() {
+    for hasher in [HashKind::Sha256, HashKind::Keccak256, HashKind::Poseidon] {
+        let mut tree = OffchainMerkleTree::new(DomainSeparated(hasher), 5, false);
+        assert_eq!(tree.root(), tree.hasher.zero_hashes(5)[5]);
+
+        for i in 0..20 {
+            assert_eq!(tree.insert_leaf(leaf(i)), Ok(i));
+        }
+        tree.replace_leaf(3, leaf(3), leaf(100)).unwrap();
+        tree.remove_leaf(7, leaf(7)).unwrap();
+
+        let root = tree.root();
+        for index in 0..20 {
+            let expected = match index {
+                3 => leaf(100),
+                7 => continue,
+                _ => leaf(index),
+            };
+            let proof = tree.proof(index).unwrap();
+            assert_eq!(proof.len(), 5);
+            assert!(verify_proof(
+                &tree.hasher,
+                &root,
+                &expected,
+                index,
+                &proof,
+                5
+            ));
+        }
+        assert_eq!(tree.leaf(7), Some(EMPTY_NODE));
+        // there are only 32 slots
+        assert_eq!(tree.leaf(32), None);
+        assert_eq!(tree.proof(32), None);
+    }
+}
+
+#[test]
+fn batch_insert_matches_single_inserts() {
+    let leaves: Vec<[u8; 32]> = (0..6).map(leaf).collect();
+    let mut single = OffchainMerkleTree::new(HashKind::Sha256, 4, false);
+    for leaf in &leaves {
+        single.insert_leaf(*leaf).unwrap();
+    }
+    let mut batch = OffchainMerkleTree::new(HashKind::Sha256, 4, false);
+    assert_eq!(batch.insert_leaves(&leaves), Ok(0));
+    assert_eq!(batch, single);
+
+    let copy = OffchainMerkleTree::from_nodes(HashKind::Sha256, 4, false, 6, batch.nodes.clone());
+    assert_eq!(copy, Some(batch.clone()));
+    // nodes of another depth, or more leaves than slots
+    assert_eq!(
+        OffchainMerkleTree::from_nodes(HashKind::Sha256, 5, false, 6, batch.nodes.clone()),
+        None
+    );
+    assert_eq!(
+        OffchainMerkleTree::from_nodes(HashKind::Sha256, 4, false, 17, batch.nodes),
+        None
+    );
+}
+
+#[test]
+fn rejects_what_the_program_rejects() {
+    let mut tree = OffchainMerkleTree::new(HashKind::Sha256, 1, false);
+    assert_eq!(
+        tree.insert_leaf(EMPTY_NODE),
+        Err(MerkleTreeStorageError::InvalidLeaf)
+    );
+    assert_eq!(
+        tree.replace_leaf(0, EMPTY_NODE, leaf(0)),
+        Err(MerkleTreeStorageError::LeafIndexOutOfBounds)
+    );
+    assert_eq!(
+        tree.insert_leaves(&[leaf(0), leaf(1), leaf(2)]),
+        Err(MerkleTreeStorageError::TreeOverflow)
+    );
+    tree.insert_leaves(&[leaf(0), leaf(1)]).unwrap();
+    assert_eq!(
+        tree.insert_leaf(leaf(2)),
+        Err(MerkleTreeStorageError::TreeOverflow)
+    );
+    assert_eq!(
+        tree.replace_leaf(1, leaf(1), EMPTY_NODE),
+        Err(MerkleTreeStorageError::InvalidLeaf)
+    );
+    assert_eq!(
+        tree.replace_leaf(1, leaf(0), leaf(2)),
+        Err(MerkleTreeStorageError::LeafMismatch)
+    );
+    assert_eq!(
+        tree.remove_leaf(1, leaf(0)),
+        Err(MerkleTreeStorageError::LeafMismatch)
+    );
+
+    tree.remove_leaf(1, leaf(1)).unwrap();
+    assert_eq!(
+        tree.remove_leaf(1, EMPTY_NODE),
+        Err(MerkleTreeStorageError::LeafRemoved)
+    );
+    // a removed leaf stays removed unless the tree allows reuse
+    assert_eq!(
+        tree.replace_leaf(1, EMPTY_NODE, leaf(2)),
+        Err(MerkleTreeStorageError::LeafRemoved)
+    );
+    tree.allow_leaf_reuse = true;
+    assert_eq!(tree.replace_leaf(1, EMPTY_NODE, leaf(2)), Ok(()));
+}
+
+#[test]
+fn multiproofs_share_siblings() {
+    let mut tree = OffchainMerkleTree::new(DomainSeparated(HashKind::Keccak256), 5, false);
+    let leaves: Vec<[u8; 32]> = (0..20).map(leaf).collect();
+    tree.insert_leaves(&leaves).unwrap();
+    let root = tree.root();
+    let proven = |indices: &[u32]| -> Vec<(u32, [u8; 32])> {
+        indices.iter().map(|i| (*i, leaves[*i as usize])).collect()
+    };
+
+    // a single leaf's multiproof is its proof
+    assert_eq!(tree.multiproof(&[6]), tree.proof(6));
+    assert_eq!(tree.multiproof(&[6, 32]), None);
+
+    let indices = [0, 1, 2, 9, 19];
+    let multiproof = tree.multiproof(&indices).unwrap();
+    // 0 and 1 are siblings, and so are their parent and the parent of 2 and 3
+    assert_eq!(multiproof.len(), 9);
+    assert!(multiproof.len() < indices.len() * 5);
+    let verify = |leaves: &[(u32, [u8; 32])], proof: &[[u8; 32]]| {
+        verify_multiproof(&tree.hasher, &root, leaves, proof, 5)
+    };
+    assert!(verify(&proven(&indices), &multiproof));
+
+    let mut wrong_leaf = proven(&indices);
+    wrong_leaf[3].1 = leaf(10);
+    assert!(!verify(&wrong_leaf, &multiproof));
+    let mut unordered = proven(&indices);
+    unordered.swap(0, 1);
+    assert!(!verify(&unordered, &multiproof));
+    let too_long = [multiproof.as_slice(), &[EMPTY_NODE]].concat();
+    assert!(!verify(&proven(&indices), &too_long));
+    assert!(!verify(&proven(&indices), &multiproof[1..]));
+}
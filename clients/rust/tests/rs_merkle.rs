@@ -0,0 +1,117 @@
+use merkle_tree_storage::{
+    types::HashKind, DomainSeparated, Hasher, Keccak256Hasher, MerkleProof, OffchainMerkleTree,
+    RsDomainSeparatedSha256, RsKeccak256, RsSha256, Sha256Hasher, TreeHasher, EMPTY_NODE,
+};
+
+fn leaves(count: u32) -> Vec<[u8; 32]> {
+    (0..count)
+        .map(|i| Keccak256Hasher.hashv(&[&i.to_le_bytes()]))
+        .collect()
+}
+
+// rs_merkle tree over the nodes the program stores for `leaves`
+fn build_rs_tree<T: rs_merkle::Hasher<Hash = [u8; 32]>>(
+    hasher: &impl Hasher,
+    leaves: &[[u8; 32]],
+) -> rs_merkle::MerkleTree<T> {
+    let nodes: Vec<[u8; 32]> = leaves.iter().map(|leaf| hasher.hash_leaf(leaf)).collect();
+    rs_merkle::MerkleTree::from_leaves(&nodes)
+}
+
+fn check_full_tree<T: rs_merkle::Hasher<Hash = [u8; 32]>>(tree_hasher: TreeHasher) {
+    let leaves = leaves(8);
+    let mut tree = OffchainMerkleTree::new(tree_hasher, 3, false);
+    tree.insert_leaves(&leaves).unwrap();
+    let rs_tree = build_rs_tree::<T>(&tree_hasher, &leaves);
+    assert_eq!(rs_tree.root(), Some(tree.root()));
+
+    for (index, leaf) in leaves.iter().enumerate() {
+        let rs_proof = rs_tree.proof(&[index]);
+        let proof = MerkleProof::from_rs_merkle(
+            &rs_proof,
+            *leaf,
+            index as u32,
+            3,
+            tree.root(),
+            tree_hasher,
+        );
+        assert!(proof.verify());
+        assert_eq!(proof.siblings, tree.proof(index as u32).unwrap());
+
+        assert!(proof.to_rs_merkle::<T>().verify(
+            tree.root(),
+            &[index],
+            &[tree_hasher.hash_leaf(leaf)],
+            8,
+        ));
+    }
+}
+
+#[test]
+fn full_trees_agree() {
+    check_full_tree::<RsSha256>(TreeHasher {
+        hash_kind: HashKind::Sha256,
+        domain_separated: false,
+    });
+    check_full_tree::<RsKeccak256>(TreeHasher {
+        hash_kind: HashKind::Keccak256,
+        domain_separated: false,
+    });
+    check_full_tree::<RsDomainSeparatedSha256>(TreeHasher {
+        hash_kind: HashKind::Sha256,
+        domain_separated: true,
+    });
+}
+
+#[test]
+fn partial_trees_differ() {
+    let hasher = Sha256Hasher;
+    let leaves = leaves(5);
+    let mut tree = OffchainMerkleTree::new(hasher, 3, false);
+    tree.insert_leaves(&leaves).unwrap();
+    let rs_tree = build_rs_tree::<RsSha256>(&hasher, &leaves);
+    assert_ne!(rs_tree.root(), Some(tree.root()));
+
+    // rs_merkle promotes the lone fifth leaf to the top, where the program
+    // hashes it with the roots of the empty subtrees beside it
+    let z = hasher.zero_hashes(3);
+    let left = hasher.hash_nodes(
+        &hasher.hash_nodes(&leaves[0], &leaves[1]),
+        &hasher.hash_nodes(&leaves[2], &leaves[3]),
+    );
+    assert_eq!(rs_tree.root(), Some(hasher.hash_nodes(&left, &leaves[4])));
+    let right = hasher.hash_nodes(&hasher.hash_nodes(&leaves[4], &z[0]), &z[1]);
+    assert_eq!(tree.root(), hasher.hash_nodes(&left, &right));
+
+    // padding with empty leaves up to capacity gives the program's tree
+    let mut padded = leaves.clone();
+    padded.resize(8, EMPTY_NODE);
+    let padded_tree = build_rs_tree::<RsSha256>(&hasher, &padded);
+    assert_eq!(padded_tree.root(), Some(tree.root()));
+    let proof = MerkleProof::from_rs_merkle(
+        &padded_tree.proof(&[4]),
+        leaves[4],
+        4,
+        3,
+        tree.root(),
+        TreeHasher {
+            hash_kind: HashKind::Sha256,
+            domain_separated: false,
+        },
+    );
+    assert!(proof.verify());
+}
+
+#[test]
+fn padding_matches_domain_separated_trees() {
+    let hasher = DomainSeparated(Sha256Hasher);
+    let leaves = leaves(3);
+    let mut tree = OffchainMerkleTree::new(hasher, 2, false);
+    tree.insert_leaves(&leaves).unwrap();
+
+    // empty slots hold [0; 32], not the hash of an empty leaf
+    let mut nodes: Vec<[u8; 32]> = leaves.iter().map(|leaf| hasher.hash_leaf(leaf)).collect();
+    nodes.push(EMPTY_NODE);
+    let rs_tree = rs_merkle::MerkleTree::<RsDomainSeparatedSha256>::from_leaves(&nodes);
+    assert_eq!(rs_tree.root(), Some(tree.root()));
+}
@@ -18,12 +18,25 @@ kinobi.update(
 // Update accounts.
 kinobi.update(
   new k.updateAccountsVisitor({
-    myPdaAccount: {
+    merkleTree: {
       seeds: [
-        k.constantPdaSeedNodeFromString("myPdaAccount"),
-        k.programIdPdaSeedNode(),
-        k.variablePdaSeedNode("authority", k.publicKeyTypeNode(), "The address of the authority"),
-        k.variablePdaSeedNode("name", k.stringTypeNode(), "The name of the account"),
+        k.constantPdaSeedNodeFromString("tree"),
+        k.variablePdaSeedNode("payer", k.publicKeyTypeNode(), "The account that created the tree"),
+        k.variablePdaSeedNode("treeId", k.numberTypeNode("u64"), "The tree index chosen by the payer"),
+      ],
+    },
+    incrementalMerkleTree: {
+      seeds: [
+        k.constantPdaSeedNodeFromString("incremental_tree"),
+        k.variablePdaSeedNode("payer", k.publicKeyTypeNode(), "The account that created the tree"),
+        k.variablePdaSeedNode("treeId", k.numberTypeNode("u64"), "The tree index chosen by the payer"),
+      ],
+    },
+    concurrentMerkleTree: {
+      seeds: [
+        k.constantPdaSeedNodeFromString("concurrent_tree"),
+        k.variablePdaSeedNode("payer", k.publicKeyTypeNode(), "The account that created the tree"),
+        k.variablePdaSeedNode("treeId", k.numberTypeNode("u64"), "The tree index chosen by the payer"),
       ],
     },
   })
@@ -0,0 +1,360 @@
+//! Node hashing shared by the merkle-tree-storage program and off-chain trees,
+//! so both sides compute identical roots.
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use light_poseidon::{Poseidon, PoseidonHasher as _};
+use solana_program::{hash, keccak};
+
+/// Prefix of hashed leaves in [`DomainSeparated`] trees.
+pub const LEAF_PREFIX: u8 = 0x00;
+/// Prefix of hashed internal nodes in [`DomainSeparated`] trees.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// Node standing for an empty leaf, and for any empty subtree in account data.
+pub const EMPTY_NODE: [u8; 32] = [0; 32];
+
+/// Hashing scheme of a tree. Only [`Hasher::hashv`] is required; by default leaves
+/// are stored as given and a parent is the hash of `left || right`.
+pub trait Hasher {
+    /// Hash of the concatenation of `data`, or of its slices as separate inputs
+    /// for [`PoseidonHasher`].
+    fn hashv(&self, data: &[&[u8]]) -> [u8; 32];
+
+    /// Node stored in the tree for a leaf value.
+    fn hash_leaf(&self, leaf: &[u8; 32]) -> [u8; 32] {
+        *leaf
+    }
+
+    /// Hashes two sibling nodes into their parent.
+    fn hash_nodes(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        self.hashv(&[left, right])
+    }
+
+    /// Roots of empty subtrees by height, from `0` to `max_depth`: `z[0]` is
+    /// [`EMPTY_NODE`] and `z[h + 1] = hash_nodes(z[h], z[h])`. The root of a tree
+    /// is the root of the full binary tree over all `2^max_depth` leaf slots, where
+    /// a slot that was never filled or was emptied holds `z[0]`; so any subtree
+    /// without a leaf hashes to `z[h]`.
+    fn zero_hashes(&self, max_depth: u8) -> Vec<[u8; 32]> {
+        let mut zero_hashes = Vec::with_capacity(max_depth as usize + 1);
+        zero_hashes.push(EMPTY_NODE);
+        for height in 0..max_depth as usize {
+            zero_hashes.push(self.hash_nodes(&zero_hashes[height], &zero_hashes[height]));
+        }
+        zero_hashes
+    }
+
+    /// Recomputes the root from a leaf value and its sibling path, ordered from
+    /// the leaf level up. Bit `i` of `index` tells whether the node is the right
+    /// child at level `i`.
+    fn compute_root(&self, leaf: &[u8; 32], index: u32, proof: &[[u8; 32]]) -> [u8; 32] {
+        let mut node = self.hash_leaf(leaf);
+        for (level, sibling) in proof.iter().enumerate() {
+            node = if (index >> level) & 1 == 0 {
+                self.hash_nodes(&node, sibling)
+            } else {
+                self.hash_nodes(sibling, &node)
+            };
+        }
+        node
+    }
+
+    /// Recomputes the root from several leaves and their multiproof: the siblings
+    /// their paths need that no other path provides, ordered level by level from
+    /// the leaves up, and by index within a level. `leaves` are `(index, leaf)`
+    /// pairs by strictly increasing index. Returns `None` when the leaves are out of
+    /// order or out of the tree, or when `proof` does not hold exactly the nodes needed.
+    fn compute_multiproof_root(
+        &self,
+        leaves: &[(u32, [u8; 32])],
+        proof: &[[u8; 32]],
+        max_depth: u8,
+    ) -> Option<[u8; 32]> {
+        if leaves.is_empty() || leaves.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return None;
+        }
+        let mut layer: Vec<(u32, [u8; 32])> = leaves
+            .iter()
+            .map(|(index, leaf)| (*index, self.hash_leaf(leaf)))
+            .collect();
+        let mut proof = proof.iter();
+        for _ in 0..max_depth {
+            let mut parents = Vec::with_capacity(layer.len());
+            let mut nodes = layer.iter().peekable();
+            while let Some((index, node)) = nodes.next() {
+                let parent = if index & 1 == 1 {
+                    self.hash_nodes(proof.next()?, node)
+                } else if let Some((_, sibling)) = nodes.next_if(|(next, _)| *next == index + 1) {
+                    self.hash_nodes(node, sibling)
+                } else {
+                    self.hash_nodes(node, proof.next()?)
+                };
+                parents.push((index >> 1, parent));
+            }
+            layer = parents;
+        }
+        match layer[..] {
+            [(0, root)] if proof.next().is_none() => Some(root),
+            _ => None,
+        }
+    }
+}
+
+/// Positions of the nodes making up the multiproof of the leaves at `indices`, in
+/// the order [`Hasher::compute_multiproof_root`] reads them, as `(height, index)`
+/// with `index` counted from the left of its level. `indices` must be strictly
+/// increasing.
+pub fn multiproof_positions(indices: &[u32], max_depth: u8) -> Vec<(u8, u32)> {
+    let mut positions = Vec::new();
+    let mut layer = indices.to_vec();
+    for height in 0..max_depth {
+        let mut parents = Vec::with_capacity(layer.len());
+        let mut indices = layer.iter().peekable();
+        while let Some(index) = indices.next() {
+            // a left node needs no proof node when its right sibling is proven too
+            if index & 1 == 1 || indices.next_if(|next| **next == index + 1).is_none() {
+                positions.push((height, index ^ 1));
+            }
+            parents.push(index >> 1);
+        }
+        layer = parents;
+    }
+    positions
+}
+
+/// SHA-256, the program's original scheme.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sha256Hasher;
+
+impl Hasher for Sha256Hasher {
+    fn hashv(&self, data: &[&[u8]]) -> [u8; 32] {
+        hash::hashv(data).to_bytes()
+    }
+}
+
+/// Keccak-256, as used by EVM Merkle proofs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Keccak256Hasher;
+
+impl Hasher for Keccak256Hasher {
+    fn hashv(&self, data: &[&[u8]]) -> [u8; 32] {
+        keccak::hashv(data).to_bytes()
+    }
+}
+
+/// Poseidon over the BN254 scalar field with circom's parameters, for trees checked
+/// in ZK circuits; the same hash as `solana_program::poseidon` with
+/// `Parameters::Bn254X5` and big-endian bytes. Each slice of `data` is one input,
+/// read as a big-endian number modulo the field order, rather than part of a
+/// concatenation, so leaves should be field elements.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoseidonHasher;
+
+impl Hasher for PoseidonHasher {
+    fn hashv(&self, data: &[&[u8]]) -> [u8; 32] {
+        let inputs: Vec<Fr> = data
+            .iter()
+            .map(|input| Fr::from_be_bytes_mod_order(input))
+            .collect();
+        let hash = Poseidon::<Fr>::new_circom(inputs.len())
+            .and_then(|mut poseidon| poseidon.hash(&inputs))
+            .expect("Poseidon takes 1 to 12 inputs");
+        hash.into_bigint().to_bytes_be().try_into().unwrap()
+    }
+}
+
+/// RFC 6962 style hashing: leaves are `H(0x00 || leaf)` and nodes `H(0x01 || left || right)`,
+/// so an internal node can never be passed off as a leaf.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DomainSeparated<H>(pub H);
+
+impl<H: Hasher> Hasher for DomainSeparated<H> {
+    fn hashv(&self, data: &[&[u8]]) -> [u8; 32] {
+        self.0.hashv(data)
+    }
+
+    fn hash_leaf(&self, leaf: &[u8; 32]) -> [u8; 32] {
+        self.0.hashv(&[&[LEAF_PREFIX], leaf])
+    }
+
+    fn hash_nodes(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        self.0.hashv(&[&[NODE_PREFIX], left, right])
+    }
+}
+
+/// Hashing scheme of a tree account: its `hash_kind`, used as is or, when
+/// `domain_separated` is set, through [`DomainSeparated`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TreeHasher<K> {
+    pub hash_kind: K,
+    pub domain_separated: bool,
+}
+
+impl<K: Hasher + Copy> Hasher for TreeHasher<K> {
+    fn hashv(&self, data: &[&[u8]]) -> [u8; 32] {
+        self.hash_kind.hashv(data)
+    }
+
+    fn hash_leaf(&self, leaf: &[u8; 32]) -> [u8; 32] {
+        if self.domain_separated {
+            DomainSeparated(self.hash_kind).hash_leaf(leaf)
+        } else {
+            self.hash_kind.hash_leaf(leaf)
+        }
+    }
+
+    fn hash_nodes(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        if self.domain_separated {
+            DomainSeparated(self.hash_kind).hash_nodes(left, right)
+        } else {
+            self.hash_kind.hash_nodes(left, right)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEPTH: u8 = 3;
+
+    fn leaves() -> Vec<[u8; 32]> {
+        (0..1u8 << DEPTH)
+            .map(|i| hash::hash(&[i]).to_bytes())
+            .collect()
+    }
+
+    // every node of the tree over `leaves`, level by level from the leaf nodes up
+    fn levels<H: Hasher>(hasher: &H, leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+        let mut levels = vec![leaves
+            .iter()
+            .map(|leaf| hasher.hash_leaf(leaf))
+            .collect::<Vec<_>>()];
+        for height in 0..DEPTH as usize {
+            let parents = levels[height]
+                .chunks(2)
+                .map(|pair| hasher.hash_nodes(&pair[0], &pair[1]))
+                .collect();
+            levels.push(parents);
+        }
+        levels
+    }
+
+    fn proof(levels: &[Vec<[u8; 32]>], index: u32) -> Vec<[u8; 32]> {
+        (0..DEPTH as usize)
+            .map(|height| levels[height][((index >> height) ^ 1) as usize])
+            .collect()
+    }
+
+    fn multiproof(levels: &[Vec<[u8; 32]>], indices: &[u32]) -> Vec<[u8; 32]> {
+        multiproof_positions(indices, DEPTH)
+            .into_iter()
+            .map(|(height, index)| levels[height as usize][index as usize])
+            .collect()
+    }
+
+    fn check_multiproof<H: Hasher>(hasher: &H, indices: &[u32]) {
+        let leaves = leaves();
+        let levels = levels(hasher, &leaves);
+        let root = levels[DEPTH as usize][0];
+        let pairs: Vec<(u32, [u8; 32])> = indices
+            .iter()
+            .map(|index| (*index, leaves[*index as usize]))
+            .collect();
+        let proof = multiproof(&levels, indices);
+        assert_eq!(
+            hasher.compute_multiproof_root(&pairs, &proof, DEPTH),
+            Some(root)
+        );
+        for index in indices {
+            assert_eq!(
+                hasher.compute_root(
+                    &leaves[*index as usize],
+                    *index,
+                    &self::proof(&levels, *index)
+                ),
+                root
+            );
+        }
+
+        // a missing or extra node, or a wrong leaf, does not give the root
+        if let Some((_, shorter)) = proof.split_last() {
+            assert_eq!(hasher.compute_multiproof_root(&pairs, shorter, DEPTH), None);
+        }
+        let longer = [proof.clone(), vec![root]].concat();
+        assert_eq!(hasher.compute_multiproof_root(&pairs, &longer, DEPTH), None);
+        let mut wrong = pairs.clone();
+        wrong[0].1 = [9; 32];
+        assert_ne!(
+            hasher.compute_multiproof_root(&wrong, &proof, DEPTH),
+            Some(root)
+        );
+    }
+
+    #[test]
+    fn single_leaf() {
+        for index in 0..1 << DEPTH {
+            assert_eq!(
+                multiproof_positions(&[index], DEPTH),
+                vec![(0, index ^ 1), (1, (index >> 1) ^ 1), (2, (index >> 2) ^ 1)]
+            );
+            check_multiproof(&Sha256Hasher, &[index]);
+            check_multiproof(&DomainSeparated(Keccak256Hasher), &[index]);
+        }
+    }
+
+    #[test]
+    fn adjacent_siblings() {
+        // leaves 2 and 3 share their parent, whose sibling covers leaves 0 and 1
+        assert_eq!(multiproof_positions(&[2, 3], DEPTH), vec![(1, 0), (2, 1)]);
+        assert_eq!(
+            multiproof_positions(&[1, 2], DEPTH),
+            vec![(0, 0), (0, 3), (2, 1)]
+        );
+        assert_eq!(
+            multiproof_positions(&[3, 4, 5], DEPTH),
+            vec![(0, 2), (1, 0), (1, 3)]
+        );
+        for indices in [&[2, 3][..], &[1, 2], &[3, 4, 5], &[0, 1, 6, 7]] {
+            check_multiproof(&Sha256Hasher, indices);
+            check_multiproof(&DomainSeparated(Sha256Hasher), indices);
+        }
+    }
+
+    #[test]
+    fn all_leaves() {
+        let indices: Vec<u32> = (0..1 << DEPTH).collect();
+        assert!(multiproof_positions(&indices, DEPTH).is_empty());
+        check_multiproof(&Sha256Hasher, &indices);
+        check_multiproof(&Keccak256Hasher, &indices);
+    }
+
+    #[test]
+    fn duplicate_and_unsorted_indices() {
+        let leaves = leaves();
+        let levels = levels(&Sha256Hasher, &leaves);
+        let proof = multiproof(&levels, &[2, 5]);
+
+        let duplicate = [(2, leaves[2]), (2, leaves[2]), (5, leaves[5])];
+        assert_eq!(
+            Sha256Hasher.compute_multiproof_root(&duplicate, &proof, DEPTH),
+            None
+        );
+        let unsorted = [(5, leaves[5]), (2, leaves[2])];
+        assert_eq!(
+            Sha256Hasher.compute_multiproof_root(&unsorted, &proof, DEPTH),
+            None
+        );
+        assert_eq!(
+            Sha256Hasher.compute_multiproof_root(&[], &proof, DEPTH),
+            None
+        );
+        // out of the tree
+        let outside = [(2, leaves[2]), (1 << DEPTH, leaves[5])];
+        assert_eq!(
+            Sha256Hasher.compute_multiproof_root(&outside, &proof, DEPTH),
+            None
+        );
+    }
+}
@@ -26,6 +26,45 @@ pub enum MerkleTreeStorageError {
     /// 5 - Payer must be a signer
     #[error("Payer must be a signer")]
     PayerMustBeSigner = 5,
+    /// 6 - Invalid max depth
+    #[error("Invalid max depth")]
+    InvalidMaxDepth = 6,
+    /// 7 - Invalid proof
+    #[error("Invalid proof")]
+    InvalidProof = 7,
+    /// 8 - Leaf index out of bounds
+    #[error("Leaf index out of bounds")]
+    LeafIndexOutOfBounds = 8,
+    /// 9 - Leaf does not match
+    #[error("Leaf does not match")]
+    LeafMismatch = 9,
+    /// 10 - Invalid leaf
+    #[error("Invalid leaf")]
+    InvalidLeaf = 10,
+    /// 11 - Leaf was removed
+    #[error("Leaf was removed")]
+    LeafRemoved = 11,
+    /// 12 - Signer is not allowed to modify the tree
+    #[error("Signer is not allowed to modify the tree")]
+    Unauthorized = 12,
+    /// 13 - Delegate list is full
+    #[error("Delegate list is full")]
+    DelegateListFull = 13,
+    /// 14 - Delegate not found
+    #[error("Delegate not found")]
+    DelegateNotFound = 14,
+    /// 15 - Tree is not empty
+    #[error("Tree is not empty")]
+    TreeNotEmpty = 15,
+    /// 16 - Invalid root history size
+    #[error("Invalid root history size")]
+    InvalidRootHistorySize = 16,
+    /// 17 - Invalid canopy depth
+    #[error("Invalid canopy depth")]
+    InvalidCanopyDepth = 17,
+    /// 18 - Invalid change log size
+    #[error("Invalid change log size")]
+    InvalidChangeLogSize = 18,
 }
 
 impl PrintProgramError for MerkleTreeStorageError {
@@ -0,0 +1,161 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::log::sol_log_data;
+use solana_program::program::invoke_signed;
+use solana_program::pubkey::Pubkey;
+
+use crate::instruction::{EmitEventArgs, MerkleTreeInstruction};
+
+/// Seed of the PDA signing the EmitEvent self-CPI.
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"event_authority";
+
+pub fn find_event_authority(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], program_id)
+}
+
+/// Event logged with `sol_log_data` after every change, as the Borsh encoding of
+/// this enum: a one-byte variant index followed by the event fields.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum MerkleTreeEvent {
+    TreeCreated(TreeCreated),
+    LeafInserted(LeafInserted),
+    LeavesInserted(LeavesInserted),
+    LeafReplaced(LeafReplaced),
+    LeafRemoved(LeafRemoved),
+    AuthorityProposed(AuthorityProposed),
+    AuthorityChanged(AuthorityChanged),
+    DelegateAdded(DelegateAdded),
+    DelegateRemoved(DelegateRemoved),
+    TreeClosed(TreeClosed),
+    TreeGrown(TreeGrown),
+}
+
+impl MerkleTreeEvent {
+    pub fn log(&self) {
+        sol_log_data(&[&borsh::to_vec(self).unwrap()]);
+    }
+
+    /// Emits the event as an EmitEvent inner instruction when `remaining_accounts`
+    /// hold the event authority and this program, so indexers still get it when the
+    /// logs are truncated; logs it otherwise.
+    pub fn emit(self, program_id: &Pubkey, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if remaining_accounts.len() < 2 {
+            self.log();
+            return Ok(());
+        }
+        let (event_authority, bump) = find_event_authority(program_id);
+        let event_authority_info = remaining_accounts.iter().find(|account| account.key == &event_authority);
+        let program_info = remaining_accounts.iter().find(|account| account.key == program_id);
+        match (event_authority_info, program_info) {
+            (Some(event_authority_info), Some(program_info)) => invoke_signed(
+                &Instruction {
+                    program_id: *program_id,
+                    accounts: vec![AccountMeta::new_readonly(event_authority, true)],
+                    data: borsh::to_vec(&MerkleTreeInstruction::EmitEvent(EmitEventArgs { event: self }))?,
+                },
+                &[event_authority_info.clone(), program_info.clone()],
+                &[&[EVENT_AUTHORITY_SEED, &[bump]]],
+            ),
+            _ => {
+                self.log();
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Account layout of a tree.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeKind {
+    /// `MerkleTree`, storing every node
+    Full,
+    /// `IncrementalMerkleTree`, storing only its frontier
+    Incremental,
+    /// `ConcurrentMerkleTree`, storing a change log
+    Concurrent,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TreeCreated {
+    pub tree: Pubkey,
+    pub kind: TreeKind,
+    pub max_depth: u8,
+    pub authority: Pubkey,
+    pub root: [u8; 32],
+}
+
+/// A leaf added by InsertLeaf, AppendLeaf or AppendConcurrentLeaf.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LeafInserted {
+    pub tree: Pubkey,
+    pub index: u32,
+    pub leaf: [u8; 32],
+    pub root: [u8; 32],
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LeavesInserted {
+    pub tree: Pubkey,
+    pub start_index: u32,
+    pub leaves: Vec<[u8; 32]>,
+    pub root: [u8; 32],
+}
+
+/// A leaf changed by ReplaceLeaf or ReplaceConcurrentLeaf.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LeafReplaced {
+    pub tree: Pubkey,
+    pub index: u32,
+    pub previous_leaf: [u8; 32],
+    pub new_leaf: [u8; 32],
+    pub root: [u8; 32],
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LeafRemoved {
+    pub tree: Pubkey,
+    pub index: u32,
+    pub previous_leaf: [u8; 32],
+    pub root: [u8; 32],
+}
+
+/// Transfer proposed by SetAuthority, or cancelled when `pending_authority` is default.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AuthorityProposed {
+    pub tree: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AuthorityChanged {
+    pub tree: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DelegateAdded {
+    pub tree: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DelegateRemoved {
+    pub tree: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TreeClosed {
+    pub tree: Pubkey,
+    pub destination: Pubkey,
+    pub lamports: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TreeGrown {
+    pub tree: Pubkey,
+    pub max_depth: u8,
+    pub root: [u8; 32],
+}
@@ -1,30 +1,269 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use shank::{ShankContext, ShankInstruction};
+use solana_program::pubkey::Pubkey;
+
+use crate::events::MerkleTreeEvent;
+use crate::state::HashKind;
 
 #[derive(BorshDeserialize, BorshSerialize, Clone, Debug, ShankContext, ShankInstruction)]
 #[rustfmt::skip]
 pub enum MerkleTreeInstruction {
-    /// Create Tree storage account
+    /// Create Tree storage account.
+    /// Trees bigger than 10 KiB must repeat this instruction (same payer and args) until fully allocated.
     #[account(0, writable, signer, name="payer", desc = "The account paying for the storage fees")]
     #[account(1, writable, name="tree", desc = "The address of the new account")]
     #[account(2, name="system_program", desc="The system program")]
     #[account(3, name="sysvar_rent", desc="Sysvar rent account")]
     CreateTree(CreateTreeArgs),
 
-    /// Insert Leaf
+    /// Insert Leaf. Transaction fees can be paid by any account.
+    #[account(0, signer, name="authority", desc = "The tree authority or one of its delegates")]
+    #[account(1, writable, name="tree", desc = "The tree to append the leaf to")]
+    InsertLeaf(InsertLeafArgs),
+
+    /// Verify that a leaf is stored at the given index, using an inclusion proof
+    /// against the current root or one kept in the root history. RemoveLeaf clears
+    /// the history; a leaf overwritten by ReplaceLeaf still verifies against older roots.
+    #[account(0, name="tree", desc = "The tree to verify the leaf against")]
+    VerifyLeaf(VerifyLeafArgs),
+
+    /// Replace an already inserted leaf
+    #[account(0, signer, name="authority", desc = "The tree authority")]
+    #[account(1, writable, name="tree", desc = "The tree holding the leaf")]
+    ReplaceLeaf(ReplaceLeafArgs),
+
+    /// Remove a leaf, setting it back to the empty leaf value
+    #[account(0, signer, name="authority", desc = "The tree authority")]
+    #[account(1, writable, name="tree", desc = "The tree holding the leaf")]
+    RemoveLeaf(RemoveLeafArgs),
+
+    /// Insert a contiguous run of leaves, all or nothing
+    #[account(0, signer, name="authority", desc = "The tree authority or one of its delegates")]
+    #[account(1, writable, name="tree", desc = "The tree to append the leaves to")]
+    InsertLeaves(InsertLeavesArgs),
+
+    /// Transfer the tree authority, or only propose it when `require_accept` is set.
+    /// Proposing the default pubkey cancels a pending transfer.
+    #[account(0, signer, name="authority", desc = "The current tree authority")]
+    #[account(1, writable, name="tree", desc = "The tree to transfer")]
+    SetAuthority(SetAuthorityArgs),
+
+    /// Accept a transfer proposed by SetAuthority
+    #[account(0, signer, name="new_authority", desc = "The proposed tree authority")]
+    #[account(1, writable, name="tree", desc = "The tree to transfer")]
+    AcceptAuthority,
+
+    /// Allow another key to insert leaves
+    #[account(0, signer, name="authority", desc = "The tree authority")]
+    #[account(1, writable, name="tree", desc = "The tree to delegate")]
+    AddDelegate(AddDelegateArgs),
+
+    /// Revoke a delegate added with AddDelegate
+    #[account(0, signer, name="authority", desc = "The tree authority")]
+    #[account(1, writable, name="tree", desc = "The delegated tree")]
+    RemoveDelegate(RemoveDelegateArgs),
+
+    /// Close a tree of any kind, sending its rent to the destination.
+    /// A tree whose creation calls were not all sent is closed by its payer.
+    #[account(0, signer, name="authority", desc = "The tree authority, or the payer of an unfinished tree")]
+    #[account(1, writable, name="tree", desc = "The tree to close")]
+    #[account(2, writable, name="destination", desc = "The account receiving the tree lamports")]
+    CloseTree(CloseTreeArgs),
+
+    /// Increase the depth of a tree, keeping its leaves at the same indexes.
+    /// Like CreateTree, repeat it (same args) until the account reaches its new size.
+    #[account(0, signer, name="authority", desc = "The tree authority")]
+    #[account(1, writable, signer, name="payer", desc = "The account paying for the extra storage")]
+    #[account(2, writable, name="tree", desc = "The tree to grow")]
+    #[account(3, name="system_program", desc="The system program")]
+    GrowTree(GrowTreeArgs),
+
+    /// Create an append-only tree that stores only its frontier and root
     #[account(0, writable, signer, name="payer", desc = "The account paying for the storage fees")]
     #[account(1, writable, name="tree", desc = "The address of the new account")]
-    InsertLeaf(InsertLeafArgs),
+    #[account(2, name="system_program", desc="The system program")]
+    CreateIncrementalTree(CreateIncrementalTreeArgs),
+
+    /// Append a leaf to an incremental tree, logging it for indexers
+    #[account(0, signer, name="authority", desc = "The tree authority")]
+    #[account(1, writable, name="tree", desc = "The tree to append the leaf to")]
+    AppendLeaf(AppendLeafArgs),
+
+    /// Create a tree that logs its latest changes so proofs against recent roots still apply.
+    /// Trees bigger than 10 KiB must repeat this instruction (same payer and args) until fully allocated.
+    #[account(0, writable, signer, name="payer", desc = "The account paying for the storage fees")]
+    #[account(1, writable, name="tree", desc = "The address of the new account")]
+    #[account(2, name="system_program", desc="The system program")]
+    CreateConcurrentTree(CreateConcurrentTreeArgs),
+
+    /// Append a leaf to a concurrent tree
+    #[account(0, signer, name="authority", desc = "The tree authority")]
+    #[account(1, writable, name="tree", desc = "The tree to append the leaf to")]
+    AppendConcurrentLeaf(AppendConcurrentLeafArgs),
+
+    /// Replace a leaf of a concurrent tree, with a proof against a root still in its change log.
+    /// The proof may leave out the top siblings held by the canopy.
+    #[account(0, signer, name="authority", desc = "The tree authority")]
+    #[account(1, writable, name="tree", desc = "The tree holding the leaf")]
+    ReplaceConcurrentLeaf(ReplaceConcurrentLeafArgs),
+
+    /// Verify a leaf of a concurrent tree, with a proof against a root still in its change log.
+    /// The proof may leave out the top siblings held by the canopy.
+    #[account(0, name="tree", desc = "The tree to verify the leaf against")]
+    VerifyConcurrentLeaf(VerifyConcurrentLeafArgs),
+
+    /// Record an event in the transaction's inner instructions. Only the program itself
+    /// can invoke it, signing for the event authority PDA; see `MerkleTreeEvent::emit`.
+    #[account(0, signer, name="event_authority", desc = "The event authority PDA")]
+    EmitEvent(EmitEventArgs),
+
+    /// Verify several leaves at once with a multiproof, which sends each sibling shared
+    /// by their paths only once, against the current root or one kept in the root history
+    #[account(0, name="tree", desc = "The tree to verify the leaves against")]
+    VerifyLeaves(VerifyLeavesArgs),
 }
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
 pub struct InsertLeafArgs {
-    pub leaf: [u8; 32]
+    pub leaf: [u8; 32],
 }
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
 pub struct CreateTreeArgs {
-    pub max_depth: u8
+    pub max_depth: u8,
+    pub tree_id: u64,
+    pub allow_leaf_reuse: bool, // let ReplaceLeaf fill removed leaves
+    pub authority: Option<Pubkey>, // defaults to the payer
+    pub hash_kind: HashKind,
+    pub domain_separated: bool, // hash leaves as H(0x00 || leaf) and nodes as H(0x01 || left || right)
+    pub root_history_size: u8, // previous roots still accepted by VerifyLeaf, up to MAX_ROOT_HISTORY; cleared by RemoveLeaf
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct VerifyLeafArgs {
+    pub leaf: [u8; 32],
+    pub index: u32,
+    pub proof: Vec<[u8; 32]> // sibling hashes from the leaf level up to the root
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct VerifyLeavesArgs {
+    pub indices: Vec<u32>, // strictly increasing
+    pub leaves: Vec<[u8; 32]>, // leaf at each of `indices`
+    pub proof: Vec<[u8; 32]> // siblings no other path provides, level by level from the leaves up
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct ReplaceLeafArgs {
+    pub index: u32,
+    pub previous_leaf: [u8; 32],
+    pub new_leaf: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct RemoveLeafArgs {
+    pub index: u32,
+    pub previous_leaf: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct InsertLeavesArgs {
+    pub leaves: Vec<[u8; 32]>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct SetAuthorityArgs {
+    pub new_authority: Pubkey,
+    pub require_accept: bool
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct AddDelegateArgs {
+    pub delegate: Pubkey
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct RemoveDelegateArgs {
+    pub delegate: Pubkey
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct CloseTreeArgs {
+    pub require_empty: bool // fail if any inserted leaf was not removed; incremental trees cannot remove leaves
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct GrowTreeArgs {
+    pub new_max_depth: u8
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct CreateIncrementalTreeArgs {
+    pub max_depth: u8,
+    pub tree_id: u64,
+    pub authority: Option<Pubkey>, // defaults to the payer
+    pub hash_kind: HashKind,
+    pub domain_separated: bool
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct AppendLeafArgs {
+    pub leaf: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct CreateConcurrentTreeArgs {
+    pub max_depth: u8,
+    pub tree_id: u64,
+    pub change_log_size: u8, // latest changes kept, up to MAX_CHANGE_LOGS
+    pub authority: Option<Pubkey>, // defaults to the payer
+    pub hash_kind: HashKind,
+    pub domain_separated: bool,
+    pub canopy_depth: u8, // upper levels stored, so proofs can omit as many top siblings
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct AppendConcurrentLeafArgs {
+    pub leaf: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct ReplaceConcurrentLeafArgs {
+    pub root: [u8; 32], // root the proof was built against
+    pub previous_leaf: [u8; 32],
+    pub new_leaf: [u8; 32],
+    pub index: u32,
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct VerifyConcurrentLeafArgs {
+    pub root: [u8; 32], // root the proof was built against
+    pub leaf: [u8; 32],
+    pub index: u32,
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EmitEventArgs {
+    pub event: MerkleTreeEvent
 }
@@ -1,16 +1,36 @@
-use borsh::BorshDeserialize;
-use solana_program::program::invoke_signed;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::program::{invoke, invoke_signed};
 use solana_program::program_error::ProgramError;
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey,
-    rent::Rent, system_instruction, system_program, sysvar::Sysvar,
+    account_info::AccountInfo, entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE}, hash, msg,
+    pubkey::Pubkey, rent::Rent, system_instruction, system_program, sysvar::Sysvar,
 };
-use hex;
-
+use std::ops::Range;
 use crate::error::MerkleTreeStorageError;
-use crate::instruction::accounts::{CreateTreeAccounts, InsertLeafAccounts};
-use crate::instruction::{CreateTreeArgs, InsertLeafArgs, MerkleTreeInstruction};
-use crate::state::MerkleTree;
+use crate::events::{
+    find_event_authority, AuthorityChanged, AuthorityProposed, DelegateAdded, DelegateRemoved,
+    LeafInserted, LeafRemoved, LeafReplaced, LeavesInserted, MerkleTreeEvent, TreeClosed,
+    TreeCreated, TreeGrown, TreeKind,
+};
+use crate::instruction::accounts::{
+    AcceptAuthorityAccounts, AddDelegateAccounts, AppendConcurrentLeafAccounts, AppendLeafAccounts,
+    CloseTreeAccounts, CreateConcurrentTreeAccounts, CreateIncrementalTreeAccounts,
+    CreateTreeAccounts, EmitEventAccounts, GrowTreeAccounts, InsertLeafAccounts,
+    InsertLeavesAccounts, RemoveDelegateAccounts, RemoveLeafAccounts, ReplaceConcurrentLeafAccounts,
+    ReplaceLeafAccounts, SetAuthorityAccounts, VerifyConcurrentLeafAccounts, VerifyLeafAccounts,
+    VerifyLeavesAccounts,
+};
+use crate::instruction::{
+    AddDelegateArgs, AppendConcurrentLeafArgs, AppendLeafArgs, CloseTreeArgs,
+    CreateConcurrentTreeArgs, CreateIncrementalTreeArgs, CreateTreeArgs, GrowTreeArgs,
+    InsertLeafArgs, InsertLeavesArgs, MerkleTreeInstruction, RemoveDelegateArgs, RemoveLeafArgs,
+    ReplaceConcurrentLeafArgs, ReplaceLeafArgs, SetAuthorityArgs, VerifyConcurrentLeafArgs,
+    VerifyLeafArgs, VerifyLeavesArgs,
+};
+use crate::state::{
+    ConcurrentMerkleTree, ConcurrentMerkleTreeAccount, ConcurrentMerkleTreeHeader, IncrementalMerkleTree, MerkleTree,
+    MerkleTreeAccount, MerkleTreeHeader, MerkleTreeView, MAX_DELEGATES,
+};
 
 pub fn process_instruction<'a>(
     program_id: &Pubkey,
@@ -26,62 +46,540 @@ pub fn process_instruction<'a>(
         },
         MerkleTreeInstruction::InsertLeaf(insert_leaf_args) => {
             insert_leaf(program_id, accounts, insert_leaf_args)
+        },
+        MerkleTreeInstruction::VerifyLeaf(verify_leaf_args) => {
+            verify_leaf(program_id, accounts, verify_leaf_args)
+        },
+        MerkleTreeInstruction::ReplaceLeaf(replace_leaf_args) => {
+            replace_leaf(program_id, accounts, replace_leaf_args)
+        },
+        MerkleTreeInstruction::RemoveLeaf(remove_leaf_args) => {
+            remove_leaf(program_id, accounts, remove_leaf_args)
+        },
+        MerkleTreeInstruction::InsertLeaves(insert_leaves_args) => {
+            insert_leaves(program_id, accounts, insert_leaves_args)
+        },
+        MerkleTreeInstruction::SetAuthority(set_authority_args) => {
+            set_authority(program_id, accounts, set_authority_args)
+        },
+        MerkleTreeInstruction::AcceptAuthority => {
+            accept_authority(program_id, accounts)
+        },
+        MerkleTreeInstruction::AddDelegate(add_delegate_args) => {
+            add_delegate(program_id, accounts, add_delegate_args)
+        },
+        MerkleTreeInstruction::RemoveDelegate(remove_delegate_args) => {
+            remove_delegate(program_id, accounts, remove_delegate_args)
+        },
+        MerkleTreeInstruction::CloseTree(close_tree_args) => {
+            close_tree(program_id, accounts, close_tree_args)
+        },
+        MerkleTreeInstruction::GrowTree(grow_tree_args) => {
+            grow_tree(program_id, accounts, grow_tree_args)
+        },
+        MerkleTreeInstruction::CreateIncrementalTree(create_incremental_tree_args) => {
+            create_incremental_tree(program_id, accounts, create_incremental_tree_args)
+        },
+        MerkleTreeInstruction::AppendLeaf(append_leaf_args) => {
+            append_leaf(program_id, accounts, append_leaf_args)
+        },
+        MerkleTreeInstruction::CreateConcurrentTree(create_concurrent_tree_args) => {
+            create_concurrent_tree(program_id, accounts, create_concurrent_tree_args)
+        },
+        MerkleTreeInstruction::AppendConcurrentLeaf(append_concurrent_leaf_args) => {
+            append_concurrent_leaf(program_id, accounts, append_concurrent_leaf_args)
+        },
+        MerkleTreeInstruction::ReplaceConcurrentLeaf(replace_concurrent_leaf_args) => {
+            replace_concurrent_leaf(program_id, accounts, replace_concurrent_leaf_args)
+        },
+        MerkleTreeInstruction::VerifyConcurrentLeaf(verify_concurrent_leaf_args) => {
+            verify_concurrent_leaf(program_id, accounts, verify_concurrent_leaf_args)
+        },
+        MerkleTreeInstruction::EmitEvent(_) => {
+            emit_event(program_id, accounts)
+        },
+        MerkleTreeInstruction::VerifyLeaves(verify_leaves_args) => {
+            verify_leaves(program_id, accounts, verify_leaves_args)
         }
     }
 }
 
 fn insert_leaf<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], insert_leaf_args: InsertLeafArgs) -> ProgramResult {
     let ctx = InsertLeafAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
 
-    let (expected_pda, _bump) = Pubkey::find_program_address(
-        &[b"tree", ctx.accounts.payer.key.as_ref()],
-        program_id,
-    );
-    if &expected_pda != ctx.accounts.tree.key {
-        msg!("event: error description: PDA {} is not belongs to payer {}", ctx.accounts.tree.key, ctx.accounts.payer.key);
-        return Err(MerkleTreeStorageError::InvalidPDA.into());
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = MerkleTreeAccount::load(&mut data)?;
+    check_inserter(&tree.header, ctx.accounts.authority)?;
+    let index = tree.header.next_leaf_index;
+    tree.insert_leaf(insert_leaf_args.leaf)?;
+    tree.save()?;
+    MerkleTreeEvent::LeafInserted(LeafInserted {
+        tree: *ctx.accounts.tree.key,
+        index,
+        leaf: insert_leaf_args.leaf,
+        root: tree.root(),
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+fn insert_leaves<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], insert_leaves_args: InsertLeavesArgs) -> ProgramResult {
+    let ctx = InsertLeavesAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = MerkleTreeAccount::load(&mut data)?;
+    check_inserter(&tree.header, ctx.accounts.authority)?;
+    let start_index = tree.header.next_leaf_index;
+    tree.insert_leaves(&insert_leaves_args.leaves)?;
+    tree.save()?;
+    MerkleTreeEvent::LeavesInserted(LeavesInserted {
+        tree: *ctx.accounts.tree.key,
+        start_index,
+        root: tree.root(),
+        leaves: insert_leaves_args.leaves,
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+fn replace_leaf<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], replace_leaf_args: ReplaceLeafArgs) -> ProgramResult {
+    let ctx = ReplaceLeafAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = MerkleTreeAccount::load(&mut data)?;
+    check_authority(&tree.header, ctx.accounts.authority)?;
+    tree.replace_leaf(replace_leaf_args.index, replace_leaf_args.previous_leaf, replace_leaf_args.new_leaf)?;
+    tree.save()?;
+    MerkleTreeEvent::LeafReplaced(LeafReplaced {
+        tree: *ctx.accounts.tree.key,
+        index: replace_leaf_args.index,
+        previous_leaf: replace_leaf_args.previous_leaf,
+        new_leaf: replace_leaf_args.new_leaf,
+        root: tree.root(),
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+fn remove_leaf<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], remove_leaf_args: RemoveLeafArgs) -> ProgramResult {
+    let ctx = RemoveLeafAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = MerkleTreeAccount::load(&mut data)?;
+    check_authority(&tree.header, ctx.accounts.authority)?;
+    tree.remove_leaf(remove_leaf_args.index, remove_leaf_args.previous_leaf)?;
+    tree.save()?;
+    MerkleTreeEvent::LeafRemoved(LeafRemoved {
+        tree: *ctx.accounts.tree.key,
+        index: remove_leaf_args.index,
+        previous_leaf: remove_leaf_args.previous_leaf,
+        root: tree.root(),
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+fn set_authority<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], set_authority_args: SetAuthorityArgs) -> ProgramResult {
+    let ctx = SetAuthorityAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = MerkleTreeAccount::load(&mut data)?;
+    check_authority(&tree.header, ctx.accounts.authority)?;
+    if set_authority_args.require_accept {
+        tree.header.pending_authority = set_authority_args.new_authority;
+        MerkleTreeEvent::AuthorityProposed(AuthorityProposed {
+            tree: *ctx.accounts.tree.key,
+            pending_authority: set_authority_args.new_authority,
+        })
+        .emit(program_id, ctx.remaining_accounts)?;
+    } else {
+        if set_authority_args.new_authority == Pubkey::default() {
+            msg!("event: error description: the default pubkey cannot be the tree authority");
+            return Err(ProgramError::InvalidArgument);
+        }
+        tree.header.authority = set_authority_args.new_authority;
+        tree.header.pending_authority = Pubkey::default();
+        MerkleTreeEvent::AuthorityChanged(AuthorityChanged {
+            tree: *ctx.accounts.tree.key,
+            authority: set_authority_args.new_authority,
+        })
+        .emit(program_id, ctx.remaining_accounts)?;
     }
-    if ctx.accounts.tree.owner != program_id {
-        msg!("event: error description: tree account owner {} is not program id {}", ctx.accounts.tree.owner, program_id);
+    tree.save()
+}
+
+fn accept_authority<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) -> ProgramResult {
+    let ctx = AcceptAuthorityAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = MerkleTreeAccount::load(&mut data)?;
+    if tree.header.pending_authority == Pubkey::default() || &tree.header.pending_authority != ctx.accounts.new_authority.key {
+        msg!("event: error description: {} is not the pending authority", ctx.accounts.new_authority.key);
+        return Err(MerkleTreeStorageError::Unauthorized.into());
+    }
+    if !ctx.accounts.new_authority.is_signer {
+        msg!("event: error description: new authority {} is not a signer", ctx.accounts.new_authority.key);
+        return Err(MerkleTreeStorageError::PayerMustBeSigner.into());
+    }
+    tree.header.authority = tree.header.pending_authority;
+    tree.header.pending_authority = Pubkey::default();
+    MerkleTreeEvent::AuthorityChanged(AuthorityChanged {
+        tree: *ctx.accounts.tree.key,
+        authority: tree.header.authority,
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    tree.save()
+}
+
+fn add_delegate<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], add_delegate_args: AddDelegateArgs) -> ProgramResult {
+    let ctx = AddDelegateAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = MerkleTreeAccount::load(&mut data)?;
+    check_authority(&tree.header, ctx.accounts.authority)?;
+    if add_delegate_args.delegate == Pubkey::default() {
+        msg!("event: error description: the default pubkey cannot be a delegate");
+        return Err(ProgramError::InvalidArgument);
+    }
+    tree.header.add_delegate(add_delegate_args.delegate)?;
+    MerkleTreeEvent::DelegateAdded(DelegateAdded {
+        tree: *ctx.accounts.tree.key,
+        delegate: add_delegate_args.delegate,
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    tree.save()
+}
+
+fn remove_delegate<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], remove_delegate_args: RemoveDelegateArgs) -> ProgramResult {
+    let ctx = RemoveDelegateAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = MerkleTreeAccount::load(&mut data)?;
+    check_authority(&tree.header, ctx.accounts.authority)?;
+    tree.header.remove_delegate(&remove_delegate_args.delegate)?;
+    MerkleTreeEvent::DelegateRemoved(DelegateRemoved {
+        tree: *ctx.accounts.tree.key,
+        delegate: remove_delegate_args.delegate,
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    tree.save()
+}
+
+fn close_tree<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], close_tree_args: CloseTreeArgs) -> ProgramResult {
+    let ctx = CloseTreeAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+    if ctx.accounts.destination.key == ctx.accounts.tree.key {
+        msg!("event: error description: the tree cannot receive its own lamports");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    {
+        let mut data = ctx.accounts.tree.data.borrow_mut();
+        // any kind of tree can be closed, and an unfinished one by its payer
+        let (authority, is_empty) = MerkleTreeAccount::load(&mut data)
+            .map(|tree| (tree.header.authority, tree.header.active_leaf_count == 0))
+            .or_else(|_| IncrementalMerkleTree::load(&data).map(|tree| (tree.authority, tree.next_leaf_index == 0)))
+            .or_else(|_| ConcurrentMerkleTreeAccount::load(&mut data).map(|tree| (tree.header.authority, tree.is_empty())))
+            .or_else(|error| unfinished_tree_payer(&data).map(|payer| (payer, true)).ok_or(error))?;
+        if &authority != ctx.accounts.authority.key {
+            msg!("event: error description: {} is not the tree authority {}", ctx.accounts.authority.key, authority);
+            return Err(MerkleTreeStorageError::Unauthorized.into());
+        }
+        check_signer(ctx.accounts.authority)?;
+        if close_tree_args.require_empty && !is_empty {
+            msg!("event: error description: tree still holds leaves");
+            return Err(MerkleTreeStorageError::TreeNotEmpty.into());
+        }
+    }
+
+    // The runtime only lets a program give an account away once its data is zeroed.
+    ctx.accounts.tree.data.borrow_mut().fill(0);
+    ctx.accounts.tree.realloc(0, false)?;
+    ctx.accounts.tree.assign(&system_program::id());
+
+    let lamports = ctx.accounts.tree.lamports();
+    **ctx.accounts.destination.lamports.borrow_mut() += lamports;
+    **ctx.accounts.tree.lamports.borrow_mut() = 0;
+    MerkleTreeEvent::TreeClosed(TreeClosed {
+        tree: *ctx.accounts.tree.key,
+        destination: *ctx.accounts.destination.key,
+        lamports,
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+fn grow_tree<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], grow_tree_args: GrowTreeArgs) -> ProgramResult {
+    let ctx = GrowTreeAccounts::context(accounts)?;
+    if *ctx.accounts.system_program.key != system_program::id() {
+        return Err(MerkleTreeStorageError::InvalidSystemProgram.into());
+    }
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+    MerkleTree::check_max_depth(grow_tree_args.new_max_depth)?;
+
+    let (max_depth, root_history_size) = {
+        let mut data = ctx.accounts.tree.data.borrow_mut();
+        let tree = MerkleTreeAccount::load(&mut data)?;
+        check_authority(&tree.header, ctx.accounts.authority)?;
+        if grow_tree_args.new_max_depth <= tree.header.max_depth {
+            msg!("event: error description: tree already has depth {}", tree.header.max_depth);
+            return Err(MerkleTreeStorageError::InvalidMaxDepth.into());
+        }
+        (tree.header.max_depth, tree.root_history.len())
+    };
+
+    // Fund the final size up front, then grow the data by at most
+    // MAX_PERMITTED_DATA_INCREASE bytes per call. Until the last call, the hash of the
+    // args sits right after the tree, and every call must repeat the same args.
+    let space = MerkleTree::get_tree_size_bytes(grow_tree_args.new_max_depth, root_history_size);
+    let tree_size = MerkleTree::get_tree_size_bytes(max_depth, root_history_size);
+    let args_hash = hash::hash(&grow_tree_args.try_to_vec()?).to_bytes();
+    let args_hash_range = tree_size..tree_size + 32;
+    if ctx.accounts.tree.data_len() > tree_size && ctx.accounts.tree.data.borrow()[args_hash_range.clone()] != args_hash {
+        msg!("event: error description: args differ from the ones tree {} started growing with", ctx.accounts.tree.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+    let lamports = Rent::get()?.minimum_balance(space);
+    let tree_lamports = ctx.accounts.tree.lamports();
+    if tree_lamports < lamports {
+        invoke(
+            &system_instruction::transfer(ctx.accounts.payer.key, ctx.accounts.tree.key, lamports - tree_lamports),
+            &[
+                ctx.accounts.payer.clone(),
+                ctx.accounts.tree.clone(),
+                ctx.accounts.system_program.clone(),
+            ],
+        )?;
+    }
+    let allocated = ctx.accounts.tree.data_len();
+    if allocated < space {
+        ctx.accounts.tree.realloc(space.min(allocated + MAX_PERMITTED_DATA_INCREASE), true)?;
+    }
+    if ctx.accounts.tree.data_len() < space {
+        ctx.accounts.tree.data.borrow_mut()[args_hash_range].copy_from_slice(&args_hash);
+        msg!("GrowTree allocated:{}/{}", ctx.accounts.tree.data_len(), space);
+        return Ok(());
+    }
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    data[args_hash_range].fill(0);
+    let tree = MerkleTreeAccount::grow(&mut data, grow_tree_args.new_max_depth)?;
+    MerkleTreeEvent::TreeGrown(TreeGrown {
+        tree: *ctx.accounts.tree.key,
+        max_depth: grow_tree_args.new_max_depth,
+        root: tree.root(),
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+fn check_tree_owner(program_id: &Pubkey, tree: &AccountInfo) -> ProgramResult {
+    if tree.owner != program_id {
+        msg!("event: error description: tree account owner {} is not program id {}", tree.owner, program_id);
         return Err(MerkleTreeStorageError::InvalidPDA.into());
     }
-    if !ctx.accounts.payer.is_signer {
-        msg!("event: error description: payer {} is not a signer", ctx.accounts.payer.key);
+    Ok(())
+}
+
+// The stored authority must sign every change to the tree.
+fn check_authority(header: &MerkleTreeHeader, authority: &AccountInfo) -> ProgramResult {
+    if &header.authority != authority.key {
+        msg!("event: error description: {} is not the tree authority {}", authority.key, header.authority);
+        return Err(MerkleTreeStorageError::Unauthorized.into());
+    }
+    check_signer(authority)
+}
+
+// Inserts can also be signed by a delegate.
+fn check_inserter(header: &MerkleTreeHeader, signer: &AccountInfo) -> ProgramResult {
+    if &header.authority != signer.key && !header.is_delegate(signer.key) {
+        msg!("event: error description: {} is neither the tree authority nor a delegate", signer.key);
+        return Err(MerkleTreeStorageError::Unauthorized.into());
+    }
+    check_signer(signer)
+}
+
+fn check_signer(signer: &AccountInfo) -> ProgramResult {
+    if !signer.is_signer {
+        msg!("event: error description: authority {} is not a signer", signer.key);
         return Err(MerkleTreeStorageError::PayerMustBeSigner.into());
     }
+    Ok(())
+}
 
-    let mut tree = MerkleTree::load(ctx.accounts.tree)?;
-    tree.insert_leaf(insert_leaf_args.leaf)?;
-    tree.save(ctx.accounts.tree)?;
-    msg!("event:LeafInserted NewRoot: {}", hex::encode(tree.nodes[0]));
+fn verify_leaf<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], verify_leaf_args: VerifyLeafArgs) -> ProgramResult {
+    let ctx = VerifyLeafAccounts::context(accounts)?;
+
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    // The tree is only read here, so it can be passed as a read-only account.
+    let data = ctx.accounts.tree.try_borrow_data()?;
+    let tree = MerkleTreeView::load(&data)?;
+    tree.verify_leaf(verify_leaf_args.leaf, verify_leaf_args.index, &verify_leaf_args.proof)?;
+    Ok(())
+}
+
+fn verify_leaves<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], verify_leaves_args: VerifyLeavesArgs) -> ProgramResult {
+    let ctx = VerifyLeavesAccounts::context(accounts)?;
+
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let data = ctx.accounts.tree.try_borrow_data()?;
+    let tree = MerkleTreeView::load(&data)?;
+    tree.verify_leaves(&verify_leaves_args.indices, &verify_leaves_args.leaves, &verify_leaves_args.proof)?;
     Ok(())
 }
 
 fn create_tree<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], create_tree_args: CreateTreeArgs) -> ProgramResult {
     // Accounts.
     let ctx = CreateTreeAccounts::context(accounts)?;
-    let rent = Rent::get()?;
 
     // Guards.
     if *ctx.accounts.system_program.key != system_program::id() {
         return Err(MerkleTreeStorageError::InvalidSystemProgram.into());
     }
+    MerkleTree::check_max_depth(create_tree_args.max_depth)?;
+    let root_history_size = create_tree_args.root_history_size as usize;
+    MerkleTree::check_root_history_size(root_history_size)?;
+
+    let space: usize = MerkleTree::get_tree_size_bytes(create_tree_args.max_depth, root_history_size);
+    let (expected_pda, bump) = MerkleTree::find_pda(program_id, ctx.accounts.payer.key, create_tree_args.tree_id);
+    if &expected_pda != ctx.accounts.tree.key {
+        msg!("event: error description: PDA {} is not belongs to payer {}", ctx.accounts.tree.key, ctx.accounts.payer.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let seeds: &[&[u8]] = &[
+        MerkleTree::PREFIX,
+        ctx.accounts.payer.key.as_ref(),
+        &create_tree_args.tree_id.to_le_bytes(),
+        &[bump],
+    ];
+    if !allocate_tree(program_id, ctx.accounts.payer, ctx.accounts.tree, ctx.accounts.system_program, seeds, &create_tree_args.try_to_vec()?, space)? {
+        msg!("CreateTree allocated:{}/{}", ctx.accounts.tree.data_len(), space);
+        return Ok(());
+    }
+
+    // The new account is zeroed, so only the header and node count need writing.
+    let header = MerkleTreeHeader {
+        max_depth: create_tree_args.max_depth,
+        next_leaf_index: 0,
+        allow_leaf_reuse: create_tree_args.allow_leaf_reuse,
+        authority: create_tree_args.authority.unwrap_or(*ctx.accounts.payer.key),
+        pending_authority: Pubkey::default(),
+        delegates: [Pubkey::default(); MAX_DELEGATES],
+        active_leaf_count: 0,
+        hash_kind: create_tree_args.hash_kind,
+        domain_separated: create_tree_args.domain_separated,
+        root_history_index: 0,
+    };
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let tree = MerkleTreeAccount::initialize(&mut data, header, root_history_size)?;
+
+    MerkleTreeEvent::TreeCreated(TreeCreated {
+        tree: *ctx.accounts.tree.key,
+        kind: TreeKind::Full,
+        max_depth: header.max_depth,
+        authority: header.authority,
+        root: tree.root(),
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+// Where a partially allocated tree keeps its payer and the hash of its creation args,
+// at the end of the first chunk.
+const PAYER_RANGE: Range<usize> = MAX_PERMITTED_DATA_INCREASE - 64..MAX_PERMITTED_DATA_INCREASE - 32;
+const ARGS_HASH_RANGE: Range<usize> = MAX_PERMITTED_DATA_INCREASE - 32..MAX_PERMITTED_DATA_INCREASE;
+
+// Creates the `tree` PDA, or grows it on later calls, and returns whether it reached
+// `space` bytes. Accounts created through CPI are capped at MAX_PERMITTED_DATA_INCREASE
+// bytes, so larger trees start partially allocated (but fully funded) and grow on the
+// next calls. Every call must be signed by the payer and repeat the serialized creation
+// `args` of the first one. Until then the payer and the args hash are kept at the end
+// of the first chunk, which lets the payer close an abandoned tree.
+fn allocate_tree<'a>(program_id: &Pubkey, payer: &AccountInfo<'a>, tree: &AccountInfo<'a>, system_program: &AccountInfo<'a>, seeds: &[&[u8]], args: &[u8], space: usize) -> Result<bool, ProgramError> {
+    check_signer(payer)?;
+    let args_hash = hash::hash(args).to_bytes();
+    if tree.owner == &system_program::id() {
+        // CPI to the System Program.
+        // Fund the full size now, later calls only grow the account.
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                tree.key,
+                Rent::get()?.minimum_balance(space),
+                space.min(MAX_PERMITTED_DATA_INCREASE) as u64,
+                program_id,
+            ),
+            &[payer.clone(), tree.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+        if space > MAX_PERMITTED_DATA_INCREASE {
+            let mut data = tree.data.borrow_mut();
+            data[PAYER_RANGE].copy_from_slice(payer.key.as_ref());
+            data[ARGS_HASH_RANGE].copy_from_slice(&args_hash);
+        }
+    } else {
+        if tree.owner != program_id {
+            msg!("event: error description: tree account owner {} is not program id {}", tree.owner, program_id);
+            return Err(MerkleTreeStorageError::InvalidPDA.into());
+        }
+        let allocated = tree.data_len();
+        if allocated >= space {
+            msg!("event: error description: tree {} is already created", tree.key);
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if tree.data.borrow()[ARGS_HASH_RANGE] != args_hash {
+            msg!("event: error description: args differ from the ones tree {} was created with", tree.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        tree.realloc(space.min(allocated + MAX_PERMITTED_DATA_INCREASE), false)?;
+        if tree.data_len() >= space {
+            // the tree expects zeroed data past its header
+            tree.data.borrow_mut()[PAYER_RANGE.start..ARGS_HASH_RANGE.end].fill(0);
+        }
+    }
+    Ok(tree.data_len() >= space)
+}
+
+// Payer of a tree whose allocation by `allocate_tree` was not finished.
+fn unfinished_tree_payer(data: &[u8]) -> Option<Pubkey> {
+    if data.len() < MAX_PERMITTED_DATA_INCREASE || data[ARGS_HASH_RANGE] == [0; 32] {
+        return None;
+    }
+    Pubkey::try_from(&data[PAYER_RANGE]).ok()
+}
+
+fn create_incremental_tree<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], create_incremental_tree_args: CreateIncrementalTreeArgs) -> ProgramResult {
+    let ctx = CreateIncrementalTreeAccounts::context(accounts)?;
+    let rent = Rent::get()?;
 
-    // Fetch the space and minimum lamports required for rent exemption.
-    let space: usize = MerkleTree::get_tree_size_bytes(create_tree_args.max_depth);
-    let lamports: u64 = rent.minimum_balance(space);
-    let (expected_pda, bump) = Pubkey::find_program_address(&[b"tree", ctx.accounts.payer.key.as_ref()], program_id);
+    if *ctx.accounts.system_program.key != system_program::id() {
+        return Err(MerkleTreeStorageError::InvalidSystemProgram.into());
+    }
+    IncrementalMerkleTree::check_max_depth(create_incremental_tree_args.max_depth)?;
+    let (expected_pda, bump) = IncrementalMerkleTree::find_pda(program_id, ctx.accounts.payer.key, create_incremental_tree_args.tree_id);
     if &expected_pda != ctx.accounts.tree.key {
         msg!("event: error description: PDA {} is not belongs to payer {}", ctx.accounts.tree.key, ctx.accounts.payer.key);
         return Err(ProgramError::InvalidArgument);
     }
 
-    // CPI to the System Program.
+    // The frontier is small enough to be created in a single call.
+    let space = IncrementalMerkleTree::get_size_bytes(create_incremental_tree_args.max_depth);
     invoke_signed(
         &system_instruction::create_account(
             ctx.accounts.payer.key,
             ctx.accounts.tree.key,
-            lamports,
+            rent.minimum_balance(space),
             space as u64,
             program_id,
         ),
@@ -90,17 +588,184 @@ fn create_tree<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], create_
             ctx.accounts.tree.clone(),
             ctx.accounts.system_program.clone(),
         ],
-        &[&[b"tree", ctx.accounts.payer.key.as_ref(), &[bump]]],
+        &[&[
+            IncrementalMerkleTree::PREFIX,
+            ctx.accounts.payer.key.as_ref(),
+            &create_incremental_tree_args.tree_id.to_le_bytes(),
+            &[bump],
+        ]],
     )?;
 
-    let tree = MerkleTree {
-        nodes: vec![[0; 32]; MerkleTree::get_tree_size(create_tree_args.max_depth)],
-        max_depth: create_tree_args.max_depth,
+    let tree = IncrementalMerkleTree::new(
+        create_incremental_tree_args.max_depth,
+        create_incremental_tree_args.authority.unwrap_or(*ctx.accounts.payer.key),
+        create_incremental_tree_args.hash_kind,
+        create_incremental_tree_args.domain_separated,
+    );
+    tree.save(&mut ctx.accounts.tree.data.borrow_mut())?;
+
+    MerkleTreeEvent::TreeCreated(TreeCreated {
+        tree: *ctx.accounts.tree.key,
+        kind: TreeKind::Incremental,
+        max_depth: tree.max_depth,
+        authority: tree.authority,
+        root: tree.root,
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+fn append_leaf<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], append_leaf_args: AppendLeafArgs) -> ProgramResult {
+    let ctx = AppendLeafAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = IncrementalMerkleTree::load(&data)?;
+    if tree.authority != *ctx.accounts.authority.key {
+        msg!("event: error description: {} is not the tree authority {}", ctx.accounts.authority.key, tree.authority);
+        return Err(MerkleTreeStorageError::Unauthorized.into());
+    }
+    check_signer(ctx.accounts.authority)?;
+    let index = tree.next_leaf_index;
+    tree.append_leaf(append_leaf_args.leaf)?;
+    tree.save(&mut data)?;
+    // the leaf is logged so that indexers can rebuild the tree and its proofs
+    MerkleTreeEvent::LeafInserted(LeafInserted {
+        tree: *ctx.accounts.tree.key,
+        index: index as u32,
+        leaf: append_leaf_args.leaf,
+        root: tree.root,
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+fn create_concurrent_tree<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], create_concurrent_tree_args: CreateConcurrentTreeArgs) -> ProgramResult {
+    let ctx = CreateConcurrentTreeAccounts::context(accounts)?;
+
+    if *ctx.accounts.system_program.key != system_program::id() {
+        return Err(MerkleTreeStorageError::InvalidSystemProgram.into());
+    }
+    let max_depth = create_concurrent_tree_args.max_depth;
+    let change_log_size = create_concurrent_tree_args.change_log_size as usize;
+    ConcurrentMerkleTree::check_size(max_depth, change_log_size, create_concurrent_tree_args.canopy_depth)?;
+    let (expected_pda, bump) = ConcurrentMerkleTree::find_pda(program_id, ctx.accounts.payer.key, create_concurrent_tree_args.tree_id);
+    if &expected_pda != ctx.accounts.tree.key {
+        msg!("event: error description: PDA {} is not belongs to payer {}", ctx.accounts.tree.key, ctx.accounts.payer.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let space = ConcurrentMerkleTree::get_size_bytes(max_depth, change_log_size, create_concurrent_tree_args.canopy_depth);
+    let seeds: &[&[u8]] = &[
+        ConcurrentMerkleTree::PREFIX,
+        ctx.accounts.payer.key.as_ref(),
+        &create_concurrent_tree_args.tree_id.to_le_bytes(),
+        &[bump],
+    ];
+    if !allocate_tree(program_id, ctx.accounts.payer, ctx.accounts.tree, ctx.accounts.system_program, seeds, &create_concurrent_tree_args.try_to_vec()?, space)? {
+        msg!("CreateConcurrentTree allocated:{}/{}", ctx.accounts.tree.data_len(), space);
+        return Ok(());
+    }
+
+    let header = ConcurrentMerkleTreeHeader {
+        max_depth,
+        authority: create_concurrent_tree_args.authority.unwrap_or(*ctx.accounts.payer.key),
+        hash_kind: create_concurrent_tree_args.hash_kind,
+        domain_separated: create_concurrent_tree_args.domain_separated,
+        sequence_number: 0,
         next_leaf_index: 0,
+        active_index: 0,
+        buffer_size: 1,
     };
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let tree = ConcurrentMerkleTreeAccount::initialize(&mut data, header, change_log_size, create_concurrent_tree_args.canopy_depth)?;
+
+    MerkleTreeEvent::TreeCreated(TreeCreated {
+        tree: *ctx.accounts.tree.key,
+        kind: TreeKind::Concurrent,
+        max_depth,
+        authority: header.authority,
+        root: tree.root(),
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+fn append_concurrent_leaf<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], append_concurrent_leaf_args: AppendConcurrentLeafArgs) -> ProgramResult {
+    let ctx = AppendConcurrentLeafAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = ConcurrentMerkleTreeAccount::load(&mut data)?;
+    check_concurrent_authority(&tree.header, ctx.accounts.authority)?;
+    let index = tree.header.next_leaf_index;
+    tree.append_leaf(append_concurrent_leaf_args.leaf)?;
+    tree.save()?;
+    MerkleTreeEvent::LeafInserted(LeafInserted {
+        tree: *ctx.accounts.tree.key,
+        index,
+        leaf: append_concurrent_leaf_args.leaf,
+        root: tree.root(),
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
+
+fn replace_concurrent_leaf<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], replace_concurrent_leaf_args: ReplaceConcurrentLeafArgs) -> ProgramResult {
+    let ctx = ReplaceConcurrentLeafAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
+
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let mut tree = ConcurrentMerkleTreeAccount::load(&mut data)?;
+    check_concurrent_authority(&tree.header, ctx.accounts.authority)?;
+    tree.replace_leaf(
+        replace_concurrent_leaf_args.root,
+        replace_concurrent_leaf_args.previous_leaf,
+        replace_concurrent_leaf_args.new_leaf,
+        replace_concurrent_leaf_args.index,
+        &replace_concurrent_leaf_args.proof,
+    )?;
+    tree.save()?;
+    MerkleTreeEvent::LeafReplaced(LeafReplaced {
+        tree: *ctx.accounts.tree.key,
+        index: replace_concurrent_leaf_args.index,
+        previous_leaf: replace_concurrent_leaf_args.previous_leaf,
+        new_leaf: replace_concurrent_leaf_args.new_leaf,
+        root: tree.root(),
+    })
+    .emit(program_id, ctx.remaining_accounts)?;
+    Ok(())
+}
 
-    tree.save(ctx.accounts.tree)?;
+fn verify_concurrent_leaf<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], verify_concurrent_leaf_args: VerifyConcurrentLeafArgs) -> ProgramResult {
+    let ctx = VerifyConcurrentLeafAccounts::context(accounts)?;
+    check_tree_owner(program_id, ctx.accounts.tree)?;
 
-    msg!("event:CreateTree address:{}", ctx.accounts.tree.key);
+    let mut data = ctx.accounts.tree.data.borrow_mut();
+    let tree = ConcurrentMerkleTreeAccount::load(&mut data)?;
+    tree.verify_leaf(
+        verify_concurrent_leaf_args.root,
+        verify_concurrent_leaf_args.leaf,
+        verify_concurrent_leaf_args.index,
+        &verify_concurrent_leaf_args.proof,
+    )?;
+    Ok(())
+}
+
+fn check_concurrent_authority(tree: &ConcurrentMerkleTreeHeader, authority: &AccountInfo) -> ProgramResult {
+    if &tree.authority != authority.key {
+        msg!("event: error description: {} is not the tree authority {}", authority.key, tree.authority);
+        return Err(MerkleTreeStorageError::Unauthorized.into());
+    }
+    check_signer(authority)
+}
+
+fn emit_event<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) -> ProgramResult {
+    let ctx = EmitEventAccounts::context(accounts)?;
+    let (event_authority, _bump) = find_event_authority(program_id);
+    if ctx.accounts.event_authority.key != &event_authority || !ctx.accounts.event_authority.is_signer {
+        msg!("event: error description: events can only be emitted by the program itself");
+        return Err(MerkleTreeStorageError::Unauthorized.into());
+    }
     Ok(())
 }
@@ -1,68 +1,1105 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use shank::ShankAccount;
-use solana_program::account_info::AccountInfo;
 use solana_program::entrypoint::ProgramResult;
-use solana_program::hash::hashv;
 use solana_program::msg;
 use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::MAX_PERMITTED_DATA_LENGTH;
+
+use merkle_tree_storage_hasher::{Hasher, Keccak256Hasher, PoseidonHasher, Sha256Hasher, TreeHasher, EMPTY_NODE};
 
 use crate::error::MerkleTreeStorageError;
 
+/// Account layout as seen by clients: the header fields followed by the
+/// Borsh-encoded node vector. The program itself never deserializes the whole
+/// account, it goes through [`MerkleTreeAccount`] instead.
+///
+/// The root is that of the full tree over all `2^max_depth` leaf slots, empty
+/// slots being `[0; 32]`. Nodes of subtrees that never held a leaf are left
+/// zeroed in `nodes` and stand for the root of an empty subtree of that height
+/// (see [`Hasher::zero_hashes`]), so a new tree's root is `zero_hashes[max_depth]`.
 #[repr(C)]
 #[derive(Clone, BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
 pub struct MerkleTree {
-    pub nodes: Vec<[u8; 32]>,
     pub max_depth: u8,
-    pub next_leaf_index: u8, // index of the next free leaf
+    pub next_leaf_index: u32, // index of the next free leaf
+    pub allow_leaf_reuse: bool, // whether ReplaceLeaf may fill a removed leaf
+    pub authority: Pubkey, // signs every change to the leaves
+    pub pending_authority: Pubkey, // proposed by SetAuthority, default when none
+    pub delegates: [Pubkey; 8], // MAX_DELEGATES keys allowed to insert, default for free slots
+    pub active_leaf_count: u32, // inserted leaves that were not removed
+    pub hash_kind: HashKind,
+    pub domain_separated: bool, // leaves and nodes hashed with RFC 6962 prefixes
+    pub root_history_index: u32, // slot of root_history written next
+    pub root_history: Vec<[u8; 32]>, // roots replaced by the latest changes, still accepted by VerifyLeaf
+    pub nodes: Vec<[u8; 32]>,
+}
+
+/// Hash function used for every internal node of a tree, chosen at creation.
+#[repr(u8)]
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, Debug, Default, PartialEq, Eq)]
+pub enum HashKind {
+    #[default]
+    Sha256,
+    Keccak256,
+    // BN254 Poseidon computed in the program, which costs far more compute units
+    // than the SHA-256 and Keccak-256 syscalls
+    Poseidon,
 }
 
+impl Hasher for HashKind {
+    fn hashv(&self, data: &[&[u8]]) -> [u8; 32] {
+        match self {
+            HashKind::Sha256 => Sha256Hasher.hashv(data),
+            HashKind::Keccak256 => Keccak256Hasher.hashv(data),
+            HashKind::Poseidon => PoseidonHasher.hashv(data),
+        }
+    }
+}
+
+/// Deepest tree whose account still fits in `MAX_PERMITTED_DATA_LENGTH` (10 MiB):
+/// depth 17 needs 2^18 - 1 nodes, about 8 MiB, while depth 18 would need 16 MiB.
+pub const MAX_DEPTH: u8 = 17;
+
+const _: () = assert!(MerkleTree::get_tree_size_bytes(MAX_DEPTH, MAX_ROOT_HISTORY) as u64 <= MAX_PERMITTED_DATA_LENGTH);
+
+/// Number of delegate slots stored in the tree header.
+pub const MAX_DELEGATES: usize = 8;
+
+/// Largest root history a tree can be created with.
+pub const MAX_ROOT_HISTORY: usize = 64;
+
 impl MerkleTree {
+    pub const PREFIX: &'static [u8] = b"tree";
+
+    /// Value of a leaf that was never inserted or was removed. It cannot be inserted as a leaf.
+    pub const EMPTY_LEAF: [u8; 32] = EMPTY_NODE;
+
+    /// Tree PDA seeds are `["tree", payer, tree_id]`, so one payer can own many trees.
+    pub fn find_pda(program_id: &Pubkey, payer: &Pubkey, tree_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::PREFIX, payer.as_ref(), &tree_id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    pub fn check_max_depth(max_depth: u8) -> Result<(), MerkleTreeStorageError> {
+        if max_depth == 0 || max_depth > MAX_DEPTH {
+            msg!("event: error description: max depth {} is not in 1..={}", max_depth, MAX_DEPTH);
+            return Err(MerkleTreeStorageError::InvalidMaxDepth);
+        }
+        Ok(())
+    }
 
-    pub fn get_tree_size(max_depth: u8) -> usize {
+    // callers must have validated `max_depth` with `check_max_depth`
+    pub const fn get_tree_size(max_depth: u8) -> usize {
         (1 << (max_depth + 1)) - 1
     }
 
-    pub fn get_tree_size_bytes(max_depth: u8) -> usize {
+    pub const fn get_tree_size_bytes(max_depth: u8, root_history_size: usize) -> usize {
         let tree_size = Self::get_tree_size(max_depth);
-        8 + tree_size * 32 + 2 // 8 bytes for vec length + bytes for all nodes + 1 byte for next_leaf_index + 1 byte for max_depth
+        // header + both vectors, each with 4 bytes for its length
+        MerkleTreeHeader::LEN + 4 + root_history_size * 32 + 4 + tree_size * 32
     }
 
-    pub fn load(account: &AccountInfo) -> Result<Self, ProgramError> {
-        let mut bytes: &[u8] = &(*account.data).borrow();
-        MerkleTree::deserialize(&mut bytes).map_err(|error| {
-            msg!("Error: {}", error);
-            MerkleTreeStorageError::DeserializationError.into()
+    pub fn check_root_history_size(root_history_size: usize) -> Result<(), MerkleTreeStorageError> {
+        if root_history_size > MAX_ROOT_HISTORY {
+            msg!("event: error description: root history size {} is above {}", root_history_size, MAX_ROOT_HISTORY);
+            return Err(MerkleTreeStorageError::InvalidRootHistorySize);
+        }
+        Ok(())
+    }
+}
+
+/// Fixed-size header stored at the start of a tree account. Must match the
+/// leading fields of [`MerkleTree`].
+#[repr(C)]
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct MerkleTreeHeader {
+    pub max_depth: u8,
+    pub next_leaf_index: u32,
+    pub allow_leaf_reuse: bool,
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub delegates: [Pubkey; 8], // MAX_DELEGATES, spelled out for shank
+    pub active_leaf_count: u32,
+    pub hash_kind: HashKind,
+    pub domain_separated: bool,
+    pub root_history_index: u32,
+}
+
+impl MerkleTreeHeader {
+    pub const LEN: usize = 1 + 4 + 1 + 32 + 32 + 32 * MAX_DELEGATES + 4 + 1 + 1 + 4;
+
+    pub fn is_delegate(&self, key: &Pubkey) -> bool {
+        *key != Pubkey::default() && self.delegates.contains(key)
+    }
+
+    /// Adding a key that is already a delegate is a no-op.
+    pub fn add_delegate(&mut self, delegate: Pubkey) -> Result<(), MerkleTreeStorageError> {
+        if self.is_delegate(&delegate) {
+            return Ok(());
+        }
+        match self.delegates.iter_mut().find(|slot| **slot == Pubkey::default()) {
+            Some(slot) => {
+                *slot = delegate;
+                Ok(())
+            }
+            None => {
+                msg!("event: error description: all {} delegate slots are used", MAX_DELEGATES);
+                Err(MerkleTreeStorageError::DelegateListFull)
+            }
+        }
+    }
+
+    pub fn remove_delegate(&mut self, delegate: &Pubkey) -> Result<(), MerkleTreeStorageError> {
+        if !self.is_delegate(delegate) {
+            msg!("event: error description: {} is not a delegate", delegate);
+            return Err(MerkleTreeStorageError::DelegateNotFound);
+        }
+        for slot in self.delegates.iter_mut().filter(|slot| *slot == delegate) {
+            *slot = Pubkey::default();
+        }
+        Ok(())
+    }
+}
+
+impl MerkleTreeHeader {
+    /// The tree hashes with its `hash_kind`, domain-separated when enabled.
+    pub fn hasher(&self) -> TreeHasher<HashKind> {
+        TreeHasher { hash_kind: self.hash_kind, domain_separated: self.domain_separated }
+    }
+}
+
+/// Zero-copy view over a tree account. Only the header is decoded; nodes are
+/// read and written in place, so an insert touches `max_depth + 1` of them.
+pub struct MerkleTreeAccount<'a> {
+    pub header: MerkleTreeHeader,
+    /// Ring of the roots replaced by the latest changes, zeroed until first written.
+    pub root_history: &'a mut [[u8; 32]],
+    pub nodes: &'a mut [[u8; 32]],
+    /// Empty subtree roots by height, computed on load with the tree's hasher.
+    pub zero_hashes: Vec<[u8; 32]>,
+    header_data: &'a mut [u8],
+}
+
+impl<'a> MerkleTreeAccount<'a> {
+    /// Writes a fresh header and vector lengths into zeroed account data.
+    pub fn initialize(data: &'a mut [u8], header: MerkleTreeHeader, root_history_size: usize) -> Result<Self, ProgramError> {
+        let (header_data, rest) = Self::split(data)?;
+        rest[..4].copy_from_slice(&(root_history_size as u32).to_le_bytes());
+        let (root_history, rest) = Self::split_root_history(rest)?;
+        let node_count = MerkleTree::get_tree_size(header.max_depth);
+        rest[..4].copy_from_slice(&(node_count as u32).to_le_bytes());
+        let mut tree = Self {
+            header,
+            root_history,
+            nodes: Self::cast_nodes(rest, node_count)?,
+            zero_hashes: header.hasher().zero_hashes(header.max_depth),
+            header_data,
+        };
+        tree.save()?;
+        Ok(tree)
+    }
+
+    pub fn load(data: &'a mut [u8]) -> Result<Self, ProgramError> {
+        let (header_data, rest) = Self::split(data)?;
+        let header = Self::read_header(header_data)?;
+        let (root_history, rest) = Self::split_root_history(rest)?;
+        let node_count = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+        if node_count != MerkleTree::get_tree_size(header.max_depth) {
+            msg!("Error: node count {} does not match depth {}", node_count, header.max_depth);
+            return Err(MerkleTreeStorageError::DeserializationError.into());
+        }
+        Ok(Self {
+            header,
+            root_history,
+            nodes: Self::cast_nodes(rest, node_count)?,
+            zero_hashes: header.hasher().zero_hashes(header.max_depth),
+            header_data,
         })
     }
 
-    pub fn save(&self, account: &AccountInfo) -> ProgramResult {
-        borsh::to_writer(&mut account.data.borrow_mut()[..], self).map_err(|error| {
+    /// Rearranges a tree into the layout of a deeper one. `data` must already be
+    /// large enough for `new_max_depth`. The old tree becomes the left-most subtree,
+    /// so leaf indexes stay valid, and only the ancestors of the old root are hashed.
+    pub fn grow(data: &'a mut [u8], new_max_depth: u8) -> Result<Self, ProgramError> {
+        let (header_data, rest) = Self::split(data)?;
+        let mut header = Self::read_header(header_data)?;
+        let (root_history, rest) = Self::split_root_history(rest)?;
+        let levels_added = (new_max_depth - header.max_depth) as u32;
+        let node_count = MerkleTree::get_tree_size(new_max_depth);
+        if rest.len() < 4 + node_count * 32 {
+            return Err(MerkleTreeStorageError::DeserializationError.into());
+        }
+        rest[..4].copy_from_slice(&(node_count as u32).to_le_bytes());
+        let nodes = Self::cast_nodes(rest, node_count)?;
+        let zero_hashes = header.hasher().zero_hashes(new_max_depth);
+
+        // Deepest level first: every level moves to higher positions than the
+        // levels below it still occupy, so nothing is overwritten before it moves.
+        for level in (0..=header.max_depth as u32).rev() {
+            let width = 1usize << level;
+            let from = width - 1;
+            nodes.copy_within(from..from + width, (width << levels_added) - 1);
+            nodes[from..from + width].fill(MerkleTree::EMPTY_LEAF);
+        }
+        // an empty tree keeps zeroed nodes, like a new one; otherwise each new
+        // ancestor's right child is an empty subtree as high as the left one
+        if header.next_leaf_index > 0 {
+            for level in (0..levels_added).rev() {
+                let node = (1usize << level) - 1;
+                let height = (new_max_depth as u32 - level - 1) as usize;
+                nodes[node] = header.hasher().hash_nodes(&nodes[2 * node + 1], &zero_hashes[height]);
+            }
+        }
+
+        header.max_depth = new_max_depth;
+        let mut tree = Self {
+            header,
+            root_history,
+            nodes,
+            zero_hashes,
+            header_data,
+        };
+        tree.save()?;
+        Ok(tree)
+    }
+
+    /// Writes the header back; nodes are already updated in place.
+    pub fn save(&mut self) -> ProgramResult {
+        borsh::to_writer(&mut self.header_data[..], &self.header).map_err(|error| {
             msg!("Error: {}", error);
             MerkleTreeStorageError::SerializationError.into()
         })
     }
 
+    pub fn root(&self) -> [u8; 32] {
+        self.node(0, self.header.max_depth as usize)
+    }
+
+    /// Value of the node at heap position `pos`, `height` levels above the leaves,
+    /// with a zeroed node read as the empty subtree it stands for.
+    pub fn node(&self, pos: usize, height: usize) -> [u8; 32] {
+        match self.nodes[pos] {
+            EMPTY_NODE => self.zero_hashes[height],
+            node => node,
+        }
+    }
+
     pub fn insert_leaf(&mut self, leaf: [u8; 32]) -> Result<(), MerkleTreeStorageError> {
-        let leaf_pos = (1 << self.max_depth) - 1 + self.next_leaf_index as usize;
-        if leaf_pos >= Self::get_tree_size(self.max_depth) {
+        if self.header.next_leaf_index as usize >= self.capacity() {
             msg!("event: error description: tree is full");
             return Err(MerkleTreeStorageError::TreeOverflow);
         }
+        Self::check_not_empty(leaf)?;
+
+        self.set_leaf(self.header.next_leaf_index, self.leaf_node(leaf));
+        self.header.next_leaf_index += 1;
+        self.header.active_leaf_count += 1;
+        Ok(())
+    }
+
+    /// Appends a contiguous run of leaves, rehashing each affected ancestor once.
+    /// Nothing is written unless the whole batch fits.
+    pub fn insert_leaves(&mut self, leaves: &[[u8; 32]]) -> Result<(), MerkleTreeStorageError> {
+        if self.header.next_leaf_index as usize + leaves.len() > self.capacity() {
+            msg!("event: error description: {} leaves do not fit, {} free", leaves.len(), self.capacity() - self.header.next_leaf_index as usize);
+            return Err(MerkleTreeStorageError::TreeOverflow);
+        }
+        for leaf in leaves {
+            Self::check_not_empty(*leaf)?;
+        }
+        if leaves.is_empty() {
+            return Ok(());
+        }
+
+        self.push_root_history();
+        let first = self.capacity() - 1 + self.header.next_leaf_index as usize;
+        for (node, leaf) in self.nodes[first..first + leaves.len()].iter_mut().zip(leaves) {
+            *node = self.header.hasher().hash_leaf(leaf);
+        }
+        self.header.next_leaf_index += leaves.len() as u32;
+        self.header.active_leaf_count += leaves.len() as u32;
+
+        // walk up the levels, rehashing only the parents of the touched range
+        let (mut low, mut high, mut height) = (first, first + leaves.len() - 1, 0);
+        while low > 0 {
+            (low, high) = ((low - 1) / 2, (high - 1) / 2);
+            for parent in low..=high {
+                self.nodes[parent] = self.header.hasher().hash_nodes(&self.node(2 * parent + 1, height), &self.node(2 * parent + 2, height));
+            }
+            height += 1;
+        }
+        Ok(())
+    }
+
+    pub fn replace_leaf(&mut self, index: u32, previous_leaf: [u8; 32], new_leaf: [u8; 32]) -> Result<(), MerkleTreeStorageError> {
+        self.check_leaf(index, previous_leaf)?;
+        Self::check_not_empty(new_leaf)?;
+        if previous_leaf == MerkleTree::EMPTY_LEAF && !self.header.allow_leaf_reuse {
+            msg!("event: error description: leaf {} was removed and the tree does not allow reuse", index);
+            return Err(MerkleTreeStorageError::LeafRemoved);
+        }
+        if previous_leaf == MerkleTree::EMPTY_LEAF {
+            self.header.active_leaf_count += 1;
+        }
+
+        self.set_leaf(index, self.leaf_node(new_leaf));
+        Ok(())
+    }
+
+    pub fn remove_leaf(&mut self, index: u32, previous_leaf: [u8; 32]) -> Result<(), MerkleTreeStorageError> {
+        self.check_leaf(index, previous_leaf)?;
+        if previous_leaf == MerkleTree::EMPTY_LEAF {
+            msg!("event: error description: leaf {} is already removed", index);
+            return Err(MerkleTreeStorageError::LeafRemoved);
+        }
+
+        self.set_leaf(index, MerkleTree::EMPTY_LEAF);
+        self.header.active_leaf_count -= 1;
+        // older roots still hold the leaf, so they must not verify it anymore
+        self.clear_root_history();
+        Ok(())
+    }
+
+    // the leaf at `index` must be inserted and currently equal to `expected`
+    fn check_leaf(&self, index: u32, expected: [u8; 32]) -> Result<(), MerkleTreeStorageError> {
+        if index >= self.header.next_leaf_index {
+            msg!("event: error description: leaf {} is not inserted yet, next leaf index is {}", index, self.header.next_leaf_index);
+            return Err(MerkleTreeStorageError::LeafIndexOutOfBounds);
+        }
+        if self.leaf(index) != self.leaf_node(expected) {
+            msg!("event: error description: leaf {} is {}", index, hex::encode(self.leaf(index)));
+            return Err(MerkleTreeStorageError::LeafMismatch);
+        }
+        Ok(())
+    }
+
+    fn check_not_empty(leaf: [u8; 32]) -> Result<(), MerkleTreeStorageError> {
+        if leaf == MerkleTree::EMPTY_LEAF {
+            msg!("event: error description: the empty leaf value is reserved for removed leaves");
+            return Err(MerkleTreeStorageError::InvalidLeaf);
+        }
+        Ok(())
+    }
+
+    pub fn capacity(&self) -> usize {
+        1 << self.header.max_depth
+    }
+
+    /// Node stored for the leaf at `index`: the leaf itself, or its hash in
+    /// domain-separated trees.
+    pub fn leaf(&self, index: u32) -> [u8; 32] {
+        self.nodes[self.capacity() - 1 + index as usize]
+    }
+
+    // removed leaves are stored as the raw empty value whatever the hashing mode
+    fn leaf_node(&self, leaf: [u8; 32]) -> [u8; 32] {
+        if leaf == MerkleTree::EMPTY_LEAF {
+            return leaf;
+        }
+        self.header.hasher().hash_leaf(&leaf)
+    }
 
-        self.nodes[leaf_pos] = leaf;
+    // writes the leaf node and rehashes its path up to the root
+    fn set_leaf(&mut self, index: u32, node: [u8; 32]) {
+        self.push_root_history();
+        let leaf_pos = self.capacity() - 1 + index as usize;
+        self.nodes[leaf_pos] = node;
         let mut current = leaf_pos;
+        let mut height = 0;
 
         while current > 0 {
             let parent = (current - 1) / 2;
-            let left = self.nodes[2 * parent + 1];
-            let right = self.nodes[2 * parent + 2];
+            let left = self.node(2 * parent + 1, height);
+            let right = self.node(2 * parent + 2, height);
 
-            self.nodes[parent] = hashv(&[&left, &right]).to_bytes();
+            self.nodes[parent] = self.header.hasher().hash_nodes(&left, &right);
 
             current = parent;
+            height += 1;
         }
+    }
 
+    // keeps the root about to be replaced, overwriting the oldest one
+    fn push_root_history(&mut self) {
+        if self.root_history.is_empty() {
+            return;
+        }
+        let slot = self.header.root_history_index as usize % self.root_history.len();
+        self.root_history[slot] = self.root();
+        self.header.root_history_index = ((slot + 1) % self.root_history.len()) as u32;
+    }
+
+    fn clear_root_history(&mut self) {
+        self.root_history.fill(EMPTY_NODE);
+        self.header.root_history_index = 0;
+    }
+
+    fn read_header(header_data: &[u8]) -> Result<MerkleTreeHeader, ProgramError> {
+        let header = MerkleTreeHeader::try_from_slice(header_data).map_err(|error| {
+            msg!("Error: {}", error);
+            MerkleTreeStorageError::DeserializationError
+        })?;
+        // a zeroed header means CreateTree has not finished allocating the account
+        if header.max_depth == 0 || header.max_depth > MAX_DEPTH {
+            msg!("Error: tree is not initialized");
+            return Err(MerkleTreeStorageError::DeserializationError.into());
+        }
+        Ok(header)
+    }
+
+    fn split(data: &'a mut [u8]) -> Result<(&'a mut [u8], &'a mut [u8]), ProgramError> {
+        if data.len() < MerkleTreeHeader::LEN + 4 {
+            return Err(MerkleTreeStorageError::DeserializationError.into());
+        }
+        Ok(data.split_at_mut(MerkleTreeHeader::LEN))
+    }
+
+    // the root history vector sits between the header and the nodes
+    fn split_root_history(rest: &'a mut [u8]) -> Result<(&'a mut [[u8; 32]], &'a mut [u8]), ProgramError> {
+        let len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+        if len > MAX_ROOT_HISTORY || rest.len() < 4 + len * 32 + 4 {
+            msg!("Error: invalid root history length {}", len);
+            return Err(MerkleTreeStorageError::DeserializationError.into());
+        }
+        let (root_history, rest) = rest[4..].split_at_mut(len * 32);
+        Ok((bytemuck::cast_slice_mut(root_history), rest))
+    }
+
+    fn cast_nodes(rest: &'a mut [u8], node_count: usize) -> Result<&'a mut [[u8; 32]], ProgramError> {
+        rest.get_mut(4..4 + node_count * 32)
+            .map(bytemuck::cast_slice_mut)
+            .ok_or_else(|| MerkleTreeStorageError::DeserializationError.into())
+    }
+}
+
+/// Read-only view over a tree account, for checking proofs against its current
+/// and recent roots. Verify instructions take the tree as a read-only account, so
+/// it is loaded from a shared borrow of the data.
+pub struct MerkleTreeView<'a> {
+    pub header: MerkleTreeHeader,
+    pub root_history: &'a [[u8; 32]],
+    pub nodes: &'a [[u8; 32]],
+    /// Empty subtree roots by height, computed on load with the tree's hasher.
+    pub zero_hashes: Vec<[u8; 32]>,
+}
+
+impl<'a> MerkleTreeView<'a> {
+    /// Same checks as [`MerkleTreeAccount::load`].
+    pub fn load(data: &'a [u8]) -> Result<Self, ProgramError> {
+        if data.len() < MerkleTreeHeader::LEN + 4 {
+            return Err(MerkleTreeStorageError::DeserializationError.into());
+        }
+        let (header_data, rest) = data.split_at(MerkleTreeHeader::LEN);
+        let header = MerkleTreeAccount::read_header(header_data)?;
+        let root_history_len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+        if root_history_len > MAX_ROOT_HISTORY || rest.len() < 4 + root_history_len * 32 + 4 {
+            msg!("Error: invalid root history length {}", root_history_len);
+            return Err(MerkleTreeStorageError::DeserializationError.into());
+        }
+        let (root_history, rest) = rest[4..].split_at(root_history_len * 32);
+        let node_count = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+        if node_count != MerkleTree::get_tree_size(header.max_depth) {
+            msg!("Error: node count {} does not match depth {}", node_count, header.max_depth);
+            return Err(MerkleTreeStorageError::DeserializationError.into());
+        }
+        let nodes = rest.get(4..4 + node_count * 32).ok_or(MerkleTreeStorageError::DeserializationError)?;
+        Ok(Self {
+            header,
+            root_history: bytemuck::cast_slice(root_history),
+            nodes: bytemuck::cast_slice(nodes),
+            zero_hashes: header.hasher().zero_hashes(header.max_depth),
+        })
+    }
+
+    /// Same as [`MerkleTreeAccount::root`].
+    pub fn root(&self) -> [u8; 32] {
+        match self.nodes[0] {
+            EMPTY_NODE => self.zero_hashes[self.header.max_depth as usize],
+            root => root,
+        }
+    }
+
+    /// Whether `root` is the current root or one still kept in the root history.
+    pub fn is_known_root(&self, root: &[u8; 32]) -> bool {
+        *root == self.root() || (*root != EMPTY_NODE && self.root_history.contains(root))
+    }
+
+    pub fn verify_leaf(&self, leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> Result<(), MerkleTreeStorageError> {
+        if index >= self.header.next_leaf_index || leaf == MerkleTree::EMPTY_LEAF || proof.len() != self.header.max_depth as usize {
+            msg!("event: error description: no leaf {} with a proof of length {}", index, proof.len());
+            return Err(MerkleTreeStorageError::InvalidProof);
+        }
+        if !self.is_known_root(&self.header.hasher().compute_root(&leaf, index, proof)) {
+            msg!("event: error description: proof does not match root {} or a recent one", hex::encode(self.root()));
+            return Err(MerkleTreeStorageError::InvalidProof);
+        }
+        Ok(())
+    }
+
+    pub fn verify_leaves(&self, indices: &[u32], leaves: &[[u8; 32]], proof: &[[u8; 32]]) -> Result<(), MerkleTreeStorageError> {
+        if indices.len() != leaves.len() || indices.iter().any(|index| *index >= self.header.next_leaf_index) || leaves.contains(&MerkleTree::EMPTY_LEAF) {
+            msg!("event: error description: {} indices for {} leaves, not all inserted", indices.len(), leaves.len());
+            return Err(MerkleTreeStorageError::InvalidProof);
+        }
+        let leaves: Vec<(u32, [u8; 32])> = indices.iter().copied().zip(leaves.iter().copied()).collect();
+        match self.header.hasher().compute_multiproof_root(&leaves, proof, self.header.max_depth) {
+            Some(root) if self.is_known_root(&root) => Ok(()),
+            _ => {
+                msg!("event: error description: multiproof does not match root {} or a recent one", hex::encode(self.root()));
+                Err(MerkleTreeStorageError::InvalidProof)
+            }
+        }
+    }
+}
+
+/// Append-only tree that keeps only its frontier, like the Ethereum deposit
+/// contract: for each height, the last left-hand subtree root on the path of the
+/// next leaf. Storage is O(max_depth), so proofs must be rebuilt off-chain from
+/// the appended leaves, which are logged by every AppendLeaf. The root follows the
+/// same definition as [`MerkleTree`].
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
+pub struct IncrementalMerkleTree {
+    pub max_depth: u8,
+    pub next_leaf_index: u64, // index of the next free leaf
+    pub authority: Pubkey, // signs every append
+    pub hash_kind: HashKind,
+    pub domain_separated: bool, // leaves and nodes hashed with RFC 6962 prefixes
+    pub root: [u8; 32],
+    pub filled_subtrees: Vec<[u8; 32]>, // one per height below the root
+}
+
+/// Deepest incremental tree; its account stays around 1 KiB.
+pub const MAX_INCREMENTAL_DEPTH: u8 = 32;
+
+impl IncrementalMerkleTree {
+    pub const PREFIX: &'static [u8] = b"incremental_tree";
+
+    /// Incremental tree PDA seeds are `["incremental_tree", payer, tree_id]`.
+    pub fn find_pda(program_id: &Pubkey, payer: &Pubkey, tree_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::PREFIX, payer.as_ref(), &tree_id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    pub fn check_max_depth(max_depth: u8) -> Result<(), MerkleTreeStorageError> {
+        if max_depth == 0 || max_depth > MAX_INCREMENTAL_DEPTH {
+            msg!("event: error description: max depth {} is not in 1..={}", max_depth, MAX_INCREMENTAL_DEPTH);
+            return Err(MerkleTreeStorageError::InvalidMaxDepth);
+        }
+        Ok(())
+    }
+
+    pub const fn get_size_bytes(max_depth: u8) -> usize {
+        1 + 8 + 32 + 1 + 1 + 32 + 4 + max_depth as usize * 32
+    }
+
+    /// An empty tree: every filled subtree starts as an empty one and the root
+    /// is the empty subtree of full height.
+    pub fn new(max_depth: u8, authority: Pubkey, hash_kind: HashKind, domain_separated: bool) -> Self {
+        let mut tree = Self {
+            max_depth,
+            next_leaf_index: 0,
+            authority,
+            hash_kind,
+            domain_separated,
+            root: EMPTY_NODE,
+            filled_subtrees: Vec::new(),
+        };
+        let mut zero_hashes = tree.hasher().zero_hashes(max_depth);
+        tree.root = zero_hashes.pop().unwrap();
+        tree.filled_subtrees = zero_hashes;
+        tree
+    }
+
+    pub fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        let tree = Self::try_from_slice(data).map_err(|error| {
+            msg!("Error: {}", error);
+            MerkleTreeStorageError::DeserializationError
+        })?;
+        if tree.max_depth == 0 || tree.max_depth > MAX_INCREMENTAL_DEPTH || tree.filled_subtrees.len() != tree.max_depth as usize {
+            msg!("Error: account is not an incremental tree");
+            return Err(MerkleTreeStorageError::DeserializationError.into());
+        }
+        Ok(tree)
+    }
+
+    pub fn save(&self, data: &mut [u8]) -> ProgramResult {
+        borsh::to_writer(data, self).map_err(|error| {
+            msg!("Error: {}", error);
+            MerkleTreeStorageError::SerializationError.into()
+        })
+    }
+
+    pub fn capacity(&self) -> u64 {
+        1 << self.max_depth
+    }
+
+    /// Appends a leaf and recomputes the root from the frontier, hashing
+    /// `max_depth` times.
+    pub fn append_leaf(&mut self, leaf: [u8; 32]) -> Result<(), MerkleTreeStorageError> {
+        if self.next_leaf_index >= self.capacity() {
+            msg!("event: error description: tree is full");
+            return Err(MerkleTreeStorageError::TreeOverflow);
+        }
+        if leaf == MerkleTree::EMPTY_LEAF {
+            msg!("event: error description: the empty leaf value is reserved for empty slots");
+            return Err(MerkleTreeStorageError::InvalidLeaf);
+        }
+
+        let hasher = self.hasher();
+        let zero_hashes = hasher.zero_hashes(self.max_depth);
+        let mut node = hasher.hash_leaf(&leaf);
+        for (height, zero_hash) in zero_hashes[..self.max_depth as usize].iter().enumerate() {
+            node = if (self.next_leaf_index >> height) & 1 == 0 {
+                // a left child: it is the new frontier at this height, with an empty right sibling
+                self.filled_subtrees[height] = node;
+                hasher.hash_nodes(&node, zero_hash)
+            } else {
+                hasher.hash_nodes(&self.filled_subtrees[height], &node)
+            };
+        }
+        self.root = node;
         self.next_leaf_index += 1;
         Ok(())
     }
+
+    /// Same scheme as [`MerkleTreeHeader::hasher`].
+    pub fn hasher(&self) -> TreeHasher<HashKind> {
+        TreeHasher { hash_kind: self.hash_kind, domain_separated: self.domain_separated }
+    }
+}
+
+/// Tree that accepts changes built against a recent root, like SPL concurrent
+/// Merkle trees. Every change records the new path of its leaf in `change_logs`,
+/// a ring of the latest `change_logs.len()` changes; a proof against a root still
+/// in the ring is fast-forwarded through the changes made since. Only the proof
+/// of the next free slot is kept, so leaves must be tracked off-chain. The root
+/// follows the same definition as [`MerkleTree`].
+///
+/// The optional canopy stores the top `canopy_depth` levels below the root, level
+/// by level from the left (`2^(canopy_depth + 1) - 2` nodes), so that proofs can
+/// leave out the siblings it holds. As in [`MerkleTree`], a zeroed node stands for
+/// an empty subtree, so the canopy is never filled in.
+///
+/// This is the account layout as seen by clients; the program goes through
+/// [`ConcurrentMerkleTreeAccount`] instead.
+#[repr(C)]
+#[derive(Clone, BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
+pub struct ConcurrentMerkleTree {
+    pub max_depth: u8,
+    pub authority: Pubkey, // signs every change
+    pub hash_kind: HashKind,
+    pub domain_separated: bool, // leaves and nodes hashed with RFC 6962 prefixes
+    pub sequence_number: u64, // changes applied since creation
+    pub next_leaf_index: u32, // index of the next free leaf
+    pub active_index: u32, // slot of change_logs holding the latest change
+    pub buffer_size: u32, // slots of change_logs in use
+    pub rightmost_proof: Vec<[u8; 32]>, // sibling path of the next free leaf
+    pub change_logs: Vec<ChangeLog>,
+    pub canopy_depth: u8,
+    pub canopy: Vec<[u8; 32]>,
+}
+
+/// Path of a leaf after a change, from the leaf node up (the root excluded),
+/// with the root it produced.
+#[derive(Clone, BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct ChangeLog {
+    pub root: [u8; 32],
+    pub index: u32,
+    pub path: Vec<[u8; 32]>,
+}
+
+/// Deepest concurrent tree, so that leaf indexes fit in a `u32`.
+pub const MAX_CONCURRENT_DEPTH: u8 = 30;
+
+/// Largest change log a concurrent tree can be created with.
+pub const MAX_CHANGE_LOGS: usize = 64;
+
+/// Deepest canopy a concurrent tree can be created with, 2^18 - 2 nodes (8 MiB)
+/// only ever written along the paths of changed leaves.
+pub const MAX_CANOPY_DEPTH: u8 = 17;
+
+const _: () = assert!(
+    ConcurrentMerkleTree::get_size_bytes(MAX_CONCURRENT_DEPTH, MAX_CHANGE_LOGS, MAX_CANOPY_DEPTH) as u64 <= MAX_PERMITTED_DATA_LENGTH
+);
+
+impl ConcurrentMerkleTree {
+    pub const PREFIX: &'static [u8] = b"concurrent_tree";
+
+    /// Concurrent tree PDA seeds are `["concurrent_tree", payer, tree_id]`.
+    pub fn find_pda(program_id: &Pubkey, payer: &Pubkey, tree_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::PREFIX, payer.as_ref(), &tree_id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    pub fn check_size(max_depth: u8, change_log_size: usize, canopy_depth: u8) -> Result<(), MerkleTreeStorageError> {
+        if max_depth == 0 || max_depth > MAX_CONCURRENT_DEPTH {
+            msg!("event: error description: max depth {} is not in 1..={}", max_depth, MAX_CONCURRENT_DEPTH);
+            return Err(MerkleTreeStorageError::InvalidMaxDepth);
+        }
+        if change_log_size == 0 || change_log_size > MAX_CHANGE_LOGS {
+            msg!("event: error description: change log size {} is not in 1..={}", change_log_size, MAX_CHANGE_LOGS);
+            return Err(MerkleTreeStorageError::InvalidChangeLogSize);
+        }
+        if canopy_depth > max_depth.min(MAX_CANOPY_DEPTH) {
+            msg!("event: error description: canopy depth {} is not in 0..={}", canopy_depth, max_depth.min(MAX_CANOPY_DEPTH));
+            return Err(MerkleTreeStorageError::InvalidCanopyDepth);
+        }
+        Ok(())
+    }
+
+    pub const fn get_size_bytes(max_depth: u8, change_log_size: usize, canopy_depth: u8) -> usize {
+        let path_bytes = 4 + max_depth as usize * 32;
+        ConcurrentMerkleTreeHeader::LEN + path_bytes + 4 + change_log_size * Self::get_change_log_size_bytes(max_depth)
+            + 1 + 4 + Self::get_canopy_size(canopy_depth) * 32
+    }
+
+    /// Bytes of one Borsh-encoded [`ChangeLog`].
+    pub const fn get_change_log_size_bytes(max_depth: u8) -> usize {
+        32 + 4 + 4 + max_depth as usize * 32
+    }
+
+    /// Number of nodes in a canopy of `canopy_depth` levels.
+    pub const fn get_canopy_size(canopy_depth: u8) -> usize {
+        (1 << (canopy_depth as usize + 1)) - 2
+    }
+}
+
+/// Fixed-size header stored at the start of a concurrent tree account. Must
+/// match the leading fields of [`ConcurrentMerkleTree`].
+#[repr(C)]
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct ConcurrentMerkleTreeHeader {
+    pub max_depth: u8,
+    pub authority: Pubkey,
+    pub hash_kind: HashKind,
+    pub domain_separated: bool,
+    pub sequence_number: u64,
+    pub next_leaf_index: u32,
+    pub active_index: u32,
+    pub buffer_size: u32,
+}
+
+impl ConcurrentMerkleTreeHeader {
+    pub const LEN: usize = 1 + 32 + 1 + 1 + 8 + 4 + 4 + 4;
+
+    /// Same scheme as [`MerkleTreeHeader::hasher`].
+    pub fn hasher(&self) -> TreeHasher<HashKind> {
+        TreeHasher { hash_kind: self.hash_kind, domain_separated: self.domain_separated }
+    }
+}
+
+/// Zero-copy view over a concurrent tree account, like [`MerkleTreeAccount`]. Only
+/// the header is decoded; the rightmost proof, the change logs and the canopy are
+/// read and written in place, so even the largest trees stay within the heap.
+pub struct ConcurrentMerkleTreeAccount<'a> {
+    pub header: ConcurrentMerkleTreeHeader,
+    pub rightmost_proof: &'a mut [[u8; 32]],
+    pub canopy_depth: u8,
+    pub canopy: &'a mut [[u8; 32]],
+    /// Empty subtree roots by height, computed on load with the tree's hasher.
+    pub zero_hashes: Vec<[u8; 32]>,
+    // the Borsh-encoded change logs, `ConcurrentMerkleTree::get_change_log_size_bytes` each
+    change_logs: &'a mut [u8],
+    header_data: &'a mut [u8],
+}
+
+impl<'a> ConcurrentMerkleTreeAccount<'a> {
+    /// Writes an empty tree into zeroed account data: the vector lengths, the
+    /// proof of the first slot and a single change log entry holding the empty
+    /// root. The canopy is left zeroed.
+    pub fn initialize(data: &'a mut [u8], header: ConcurrentMerkleTreeHeader, change_log_size: usize, canopy_depth: u8) -> Result<Self, ProgramError> {
+        if data.len() != ConcurrentMerkleTree::get_size_bytes(header.max_depth, change_log_size, canopy_depth) {
+            return Err(MerkleTreeStorageError::DeserializationError.into());
+        }
+        let depth = header.max_depth as usize;
+        let write_len = |data: &mut [u8], offset: usize, len: usize| {
+            data[offset..offset + 4].copy_from_slice(&(len as u32).to_le_bytes());
+        };
+        borsh::to_writer(&mut data[..], &header).map_err(|_| MerkleTreeStorageError::SerializationError)?;
+        let mut offset = ConcurrentMerkleTreeHeader::LEN;
+        write_len(data, offset, depth);
+        offset += 4 + depth * 32;
+        write_len(data, offset, change_log_size);
+        offset += 4;
+        for _ in 0..change_log_size {
+            write_len(data, offset + 32 + 4, depth);
+            offset += ConcurrentMerkleTree::get_change_log_size_bytes(header.max_depth);
+        }
+        data[offset] = canopy_depth;
+        write_len(data, offset + 1, ConcurrentMerkleTree::get_canopy_size(canopy_depth));
+
+        let mut tree = Self::load(data)?;
+        let root = tree.zero_hashes[depth];
+        let zero_hashes = tree.zero_hashes[..depth].to_vec();
+        tree.rightmost_proof.copy_from_slice(&zero_hashes);
+        tree.write_change_log(0, root, 0, &zero_hashes);
+        Ok(tree)
+    }
+
+    pub fn load(data: &'a mut [u8]) -> Result<Self, ProgramError> {
+        let invalid = || {
+            msg!("Error: account is not a concurrent tree");
+            ProgramError::from(MerkleTreeStorageError::DeserializationError)
+        };
+        if data.len() < ConcurrentMerkleTreeHeader::LEN {
+            return Err(invalid());
+        }
+        let (header_data, rest) = data.split_at_mut(ConcurrentMerkleTreeHeader::LEN);
+        let header = ConcurrentMerkleTreeHeader::try_from_slice(header_data).map_err(|_| invalid())?;
+        // a zeroed header means CreateConcurrentTree has not finished allocating the account
+        if header.max_depth == 0 || header.max_depth > MAX_CONCURRENT_DEPTH {
+            return Err(invalid());
+        }
+        let (rightmost_proof, rest) = Self::split_nodes(rest, header.max_depth as usize).ok_or_else(invalid)?;
+
+        let (change_log_size, rest) = Self::split_len(rest).ok_or_else(invalid)?;
+        let change_log_bytes = ConcurrentMerkleTree::get_change_log_size_bytes(header.max_depth);
+        if change_log_size == 0
+            || change_log_size > MAX_CHANGE_LOGS
+            || header.active_index as usize >= change_log_size
+            || header.buffer_size == 0
+            || header.buffer_size as usize > change_log_size
+            || rest.len() < change_log_size * change_log_bytes
+        {
+            return Err(invalid());
+        }
+        let (change_logs, rest) = rest.split_at_mut(change_log_size * change_log_bytes);
+        // every path holds `max_depth` nodes
+        if change_logs.chunks(change_log_bytes).any(|change_log| change_log[36..40] != (header.max_depth as u32).to_le_bytes()) {
+            return Err(invalid());
+        }
+
+        let (canopy_depth, rest) = rest.split_first_mut().ok_or_else(invalid)?;
+        let canopy_depth = *canopy_depth;
+        if canopy_depth > header.max_depth.min(MAX_CANOPY_DEPTH) {
+            return Err(invalid());
+        }
+        let (canopy, _) = Self::split_nodes(rest, ConcurrentMerkleTree::get_canopy_size(canopy_depth)).ok_or_else(invalid)?;
+
+        Ok(Self {
+            header,
+            rightmost_proof,
+            canopy_depth,
+            canopy,
+            zero_hashes: header.hasher().zero_hashes(header.max_depth),
+            change_logs,
+            header_data,
+        })
+    }
+
+    /// Writes the header back; everything else is already updated in place.
+    pub fn save(&mut self) -> ProgramResult {
+        borsh::to_writer(&mut self.header_data[..], &self.header).map_err(|error| {
+            msg!("Error: {}", error);
+            MerkleTreeStorageError::SerializationError.into()
+        })
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.change_log_root(self.header.active_index as usize)
+    }
+
+    pub fn capacity(&self) -> u64 {
+        1 << self.header.max_depth
+    }
+
+    /// Whether every leaf slot is empty again, leaves being removed by replacing
+    /// them with the empty value.
+    pub fn is_empty(&self) -> bool {
+        self.root() == self.zero_hashes[self.header.max_depth as usize]
+    }
+
+    /// Appends a leaf to the next free slot, whose proof is the rightmost proof.
+    pub fn append_leaf(&mut self, leaf: [u8; 32]) -> Result<(), MerkleTreeStorageError> {
+        if self.header.next_leaf_index as u64 >= self.capacity() {
+            msg!("event: error description: tree is full");
+            return Err(MerkleTreeStorageError::TreeOverflow);
+        }
+        if leaf == MerkleTree::EMPTY_LEAF {
+            msg!("event: error description: the empty leaf value is reserved for empty slots");
+            return Err(MerkleTreeStorageError::InvalidLeaf);
+        }
+
+        let index = self.header.next_leaf_index;
+        let proof = self.rightmost_proof.to_vec();
+        let path = self.apply(leaf, index, &proof);
+        // The next slot shares the ancestors above the lowest zero bit of `index`:
+        // its sibling there is the node just written, and below it empty subtrees.
+        let depth = self.header.max_depth as usize;
+        let lowest_zero = (index.trailing_ones() as usize).min(depth);
+        self.rightmost_proof[..lowest_zero].copy_from_slice(&self.zero_hashes[..lowest_zero]);
+        if lowest_zero < depth {
+            self.rightmost_proof[lowest_zero] = path[lowest_zero];
+        }
+        self.header.next_leaf_index += 1;
+        Ok(())
+    }
+
+    /// Replaces the leaf at `index`, proven with `proof` against `root`, which
+    /// may be any root still in the change log. The empty leaf value removes it.
+    pub fn replace_leaf(&mut self, root: [u8; 32], previous_leaf: [u8; 32], new_leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> Result<(), MerkleTreeStorageError> {
+        let proof = self.fast_forward(root, previous_leaf, index, proof)?;
+        let path = self.apply(new_leaf, index, &proof);
+        // the next free slot's sibling where its path meets this leaf's
+        if (self.header.next_leaf_index as u64) < self.capacity() {
+            let meeting = (31 - (index ^ self.header.next_leaf_index).leading_zeros()) as usize;
+            self.rightmost_proof[meeting] = path[meeting];
+        }
+        Ok(())
+    }
+
+    /// Checks that `leaf` is at `index`, proven with `proof` against `root`,
+    /// which may be any root still in the change log.
+    /// Checks `leaf` at `index` against `root` or a later one. As with
+    /// [`MerkleTreeAccount::verify_leaf`], a removed leaf cannot be verified.
+    pub fn verify_leaf(&self, root: [u8; 32], leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> Result<(), MerkleTreeStorageError> {
+        if leaf == MerkleTree::EMPTY_LEAF {
+            msg!("event: error description: the empty leaf value is not a leaf to verify");
+            return Err(MerkleTreeStorageError::InvalidProof);
+        }
+        self.fast_forward(root, leaf, index, proof).map(|_| ())
+    }
+
+    // Completes `proof` from the canopy and updates it with every change made
+    // after `root`, then checks that it links `leaf` at `index` to the current root.
+    fn fast_forward(&self, root: [u8; 32], leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> Result<Vec<[u8; 32]>, MerkleTreeStorageError> {
+        let depth = self.header.max_depth as usize;
+        if index >= self.header.next_leaf_index || proof.len() > depth || proof.len() + (self.canopy_depth as usize) < depth {
+            msg!("event: error description: no leaf {} with a proof of length {}", index, proof.len());
+            return Err(MerkleTreeStorageError::InvalidProof);
+        }
+        let len = self.change_log_size();
+        let slot = |age: usize| (self.header.active_index as usize + len - age) % len;
+        let newer_changes = (0..self.header.buffer_size as usize)
+            .find(|age| self.change_log_root(slot(*age)) == root)
+            .ok_or_else(|| {
+                msg!("event: error description: root {} is not in the change log", hex::encode(root));
+                MerkleTreeStorageError::InvalidProof
+            })?;
+
+        // The canopy holds current nodes, which is what replaying the changes below
+        // would turn the omitted siblings into.
+        let mut proof = proof.to_vec();
+        for height in proof.len()..depth {
+            proof.push(self.canopy_node(height, (index >> height) ^ 1));
+        }
+        for age in (0..newer_changes).rev() {
+            let change_index = self.change_log_index(slot(age));
+            if change_index == index {
+                msg!("event: error description: leaf {} was changed after root {}", index, hex::encode(root));
+                return Err(MerkleTreeStorageError::LeafMismatch);
+            }
+            let meeting = (31 - (index ^ change_index).leading_zeros()) as usize;
+            proof[meeting] = self.change_log_path(slot(age))[meeting];
+        }
+        if self.path_to_root(leaf, index, &proof).1 != self.root() {
+            msg!("event: error description: proof does not match root {}", hex::encode(root));
+            return Err(MerkleTreeStorageError::InvalidProof);
+        }
+        Ok(proof)
+    }
+
+    // Nodes from `leaf` at `index` up to the root, and the root. As in
+    // [`MerkleTree`], an emptied leaf is stored as the raw empty value.
+    fn path_to_root(&self, leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> (Vec<[u8; 32]>, [u8; 32]) {
+        let hasher = self.header.hasher();
+        let mut path = Vec::with_capacity(proof.len());
+        let mut node = if leaf == MerkleTree::EMPTY_LEAF { leaf } else { hasher.hash_leaf(&leaf) };
+        for (height, sibling) in proof.iter().enumerate() {
+            path.push(node);
+            node = if (index >> height) & 1 == 0 {
+                hasher.hash_nodes(&node, sibling)
+            } else {
+                hasher.hash_nodes(sibling, &node)
+            };
+        }
+        (path, node)
+    }
+
+    // writes `leaf` at `index` given its up-to-date proof, logging the new path
+    fn apply(&mut self, leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> Vec<[u8; 32]> {
+        let (path, node) = self.path_to_root(leaf, index, proof);
+        let len = self.change_log_size() as u32;
+        self.header.active_index = (self.header.active_index + 1) % len;
+        self.header.buffer_size = (self.header.buffer_size + 1).min(len);
+        self.header.sequence_number += 1;
+        self.write_change_log(self.header.active_index as usize, node, index, &path);
+        let max_depth = self.header.max_depth;
+        for height in max_depth - self.canopy_depth..max_depth {
+            let position = self.canopy_position(height as usize, index >> height);
+            self.canopy[position] = path[height as usize];
+        }
+        path
+    }
+
+    /// Canopy node at `height` above the leaves, `offset` from the left.
+    pub fn canopy_node(&self, height: usize, offset: u32) -> [u8; 32] {
+        match self.canopy[self.canopy_position(height, offset)] {
+            EMPTY_NODE => self.zero_hashes[height],
+            node => node,
+        }
+    }
+
+    // canopy slot of the node at `height` above the leaves, `offset` from the left
+    fn canopy_position(&self, height: usize, offset: u32) -> usize {
+        let level = self.header.max_depth as usize - height;
+        (1 << level) - 2 + offset as usize
+    }
+
+    fn change_log_size(&self) -> usize {
+        self.change_logs.len() / ConcurrentMerkleTree::get_change_log_size_bytes(self.header.max_depth)
+    }
+
+    // bytes of the change log in `slot`: root, index, path length and path
+    fn change_log(&self, slot: usize) -> &[u8] {
+        let size = ConcurrentMerkleTree::get_change_log_size_bytes(self.header.max_depth);
+        &self.change_logs[slot * size..(slot + 1) * size]
+    }
+
+    fn change_log_root(&self, slot: usize) -> [u8; 32] {
+        self.change_log(slot)[..32].try_into().unwrap()
+    }
+
+    fn change_log_index(&self, slot: usize) -> u32 {
+        u32::from_le_bytes(self.change_log(slot)[32..36].try_into().unwrap())
+    }
+
+    fn change_log_path(&self, slot: usize) -> &[[u8; 32]] {
+        bytemuck::cast_slice(&self.change_log(slot)[40..])
+    }
+
+    fn write_change_log(&mut self, slot: usize, root: [u8; 32], index: u32, path: &[[u8; 32]]) {
+        let size = ConcurrentMerkleTree::get_change_log_size_bytes(self.header.max_depth);
+        let change_log = &mut self.change_logs[slot * size..(slot + 1) * size];
+        change_log[..32].copy_from_slice(&root);
+        change_log[32..36].copy_from_slice(&index.to_le_bytes());
+        change_log[40..].copy_from_slice(bytemuck::cast_slice(path));
+    }
+
+    // the Borsh length prefix at the start of `rest`
+    fn split_len(rest: &'a mut [u8]) -> Option<(usize, &'a mut [u8])> {
+        if rest.len() < 4 {
+            return None;
+        }
+        let (len, rest) = rest.split_at_mut(4);
+        Some((u32::from_le_bytes((&*len).try_into().unwrap()) as usize, rest))
+    }
+
+    // a Borsh vector of exactly `len` nodes at the start of `rest`
+    fn split_nodes(rest: &'a mut [u8], len: usize) -> Option<(&'a mut [[u8; 32]], &'a mut [u8])> {
+        let (actual_len, rest) = Self::split_len(rest)?;
+        if actual_len != len || rest.len() < len * 32 {
+            return None;
+        }
+        let (nodes, rest) = rest.split_at_mut(len * 32);
+        Some((bytemuck::cast_slice_mut(nodes), rest))
+    }
 }